@@ -0,0 +1,94 @@
+//! Measures how calls to a mock scale with the number of threads
+//! calling it, comparable to `tests/threads.rs`.
+//!
+//! Run with `cargo bench --bench threads`. Only stubs set with
+//! `then_fn` and without argument matchers or limits are invoked
+//! without locking, so only their throughput should scale close to
+//! linearly with the number of threads, up to the number of cores.
+//! `then` and `then_return` still take the stub's lock on every call
+//! and are measured for comparison.
+//!
+//! The only results so far come from a single core machine, where
+//! every stub keeps the same throughput, about 8M calls/s, no matter
+//! the number of threads, as they all share the one core. They say
+//! nothing about scaling: whether `then_fn` scales close to linearly
+//! on 8 or more cores is still unverified.
+
+// benchmarks only run on stable
+#![allow(clippy::incompatible_msrv)]
+
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+#[faux::create]
+pub struct Foo {}
+
+#[faux::methods]
+impl Foo {
+    pub fn bar(&self, _: u32) -> u32 {
+        unreachable!()
+    }
+}
+
+const CALLS_PER_THREAD: u32 = 1_000_000;
+const THREADS: [usize; 5] = [1, 2, 4, 8, 16];
+
+fn main() {
+    run("then_fn", |fake| {
+        faux::when!(fake.bar).then_fn(|n| n + 1);
+    });
+
+    run("then_fn with args", |fake| {
+        faux::when!(fake.bar(_ = faux::matcher::any())).then_fn(|n| n + 1);
+    });
+
+    run("then", |fake| {
+        faux::when!(fake.bar).then(|n| n + 1);
+    });
+
+    run("then_return", |fake| {
+        faux::when!(fake.bar).then_return(1);
+    });
+}
+
+fn run(name: &str, stub: impl Fn(&mut Foo)) {
+    println!("{name}:");
+
+    let mut single = None;
+    for threads in THREADS {
+        let mut fake = Foo::faux();
+        stub(&mut fake);
+
+        let elapsed = calls(Arc::new(fake), threads);
+        let throughput = (threads as f64 * f64::from(CALLS_PER_THREAD)) / elapsed.as_secs_f64();
+        let single = *single.get_or_insert(throughput);
+
+        println!(
+            "  {threads:>2} thread(s): {:>8.2}M calls/s ({:.2}x)",
+            throughput / 1e6,
+            throughput / single
+        );
+    }
+}
+
+fn calls(fake: Arc<Foo>, threads: usize) -> Duration {
+    let start = Instant::now();
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let fake = fake.clone();
+            thread::spawn(move || {
+                for n in 0..CALLS_PER_THREAD {
+                    std::hint::black_box(fake.bar(std::hint::black_box(n)));
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    start.elapsed()
+}
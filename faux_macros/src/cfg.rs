@@ -0,0 +1,61 @@
+use darling::FromMeta;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+/// A configuration predicate, e.g., `test` or `any(test, feature = "mocks")`
+///
+/// The mockable expansion is only compiled when the predicate is
+/// true. Otherwise, the original item is compiled untouched.
+pub struct Cfg(syn::Meta);
+
+impl FromMeta for Cfg {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        syn::parse_str(value)
+            .map(Cfg)
+            .map_err(|e| darling::Error::custom(format!("invalid cfg predicate: {e}")))
+    }
+}
+
+impl Cfg {
+    /// Gates every item in the mockable expansion behind
+    /// `#[cfg(predicate)]` and the original item behind
+    /// `#[cfg(not(predicate))]`
+    pub fn gate(&self, mockable: TokenStream, original: impl ToTokens) -> TokenStream {
+        let predicate = &self.0;
+        let mut mockable = match syn::parse2::<syn::File>(mockable) {
+            Ok(mockable) => mockable,
+            Err(e) => return e.into_compile_error(),
+        };
+
+        let gate: syn::Attribute = syn::parse_quote! { #[cfg(#predicate)] };
+        mockable
+            .items
+            .iter_mut()
+            .filter_map(attrs)
+            .for_each(|attrs| attrs.insert(0, gate.clone()));
+
+        quote! {
+            #mockable
+
+            #[cfg(not(#predicate))]
+            #original
+        }
+    }
+}
+
+fn attrs(item: &mut syn::Item) -> Option<&mut Vec<syn::Attribute>> {
+    match item {
+        syn::Item::Const(item) => Some(&mut item.attrs),
+        syn::Item::Enum(item) => Some(&mut item.attrs),
+        syn::Item::Fn(item) => Some(&mut item.attrs),
+        syn::Item::Impl(item) => Some(&mut item.attrs),
+        syn::Item::Macro(item) => Some(&mut item.attrs),
+        syn::Item::Mod(item) => Some(&mut item.attrs),
+        syn::Item::Static(item) => Some(&mut item.attrs),
+        syn::Item::Struct(item) => Some(&mut item.attrs),
+        syn::Item::Trait(item) => Some(&mut item.attrs),
+        syn::Item::Type(item) => Some(&mut item.attrs),
+        syn::Item::Use(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
@@ -1,4 +1,7 @@
-use crate::self_type::SelfType;
+#[cfg(feature = "serde")]
+mod serde;
+
+use crate::{cfg::Cfg, self_type::SelfType};
 use darling::FromMeta;
 use quote::quote;
 
@@ -7,6 +10,7 @@ use quote::quote;
 pub struct Args {
     #[darling(default)]
     self_type: SelfType,
+    pub cfg: Option<Cfg>,
 }
 
 pub struct Mockable {
@@ -14,6 +18,10 @@ pub struct Mockable {
     real: syn::ItemStruct,
     // the morphed definition, wraps the real struct around a MaybeFaux
     morphed: syn::ItemStruct,
+    // trait implementations forwarded to the real struct
+    forwarded: proc_macro2::TokenStream,
+    // how the real struct is stored inside the MaybeFaux
+    wrapped_self: proc_macro2::TokenStream,
 }
 
 impl Mockable {
@@ -24,32 +32,51 @@ impl Mockable {
         // change the name of the original struct and then stop mutating it
         let mut real = original;
         real.ident = real_struct_new_ident(&real.ident);
+
+        #[cfg(feature = "serde")]
+        let serde = serde::Derives::take(&mut morphed, &mut real);
         let real = real;
 
         // change the fields in morphed to wrap the original struct
+        let wrapped_self = {
+            let modified_name = &real.ident;
+            let (_, ty_generics, _) = morphed.generics.split_for_impl();
+
+            match args.self_type {
+                SelfType::Rc => quote! { std::rc::Rc<#modified_name #ty_generics> },
+                SelfType::Arc => quote! { std::sync::Arc<#modified_name #ty_generics> },
+                SelfType::Owned => quote! {#modified_name #ty_generics },
+                SelfType::Box => quote! { std::boxed::Box<#modified_name #ty_generics>},
+            }
+        };
+
         morphed.fields = {
-            let wrapped_self = {
-                let modified_name = &real.ident;
-                let (_, ty_generics, _) = morphed.generics.split_for_impl();
-
-                match args.self_type {
-                    SelfType::Rc => quote! { std::rc::Rc<#modified_name #ty_generics> },
-                    SelfType::Arc => quote! { std::sync::Arc<#modified_name #ty_generics> },
-                    SelfType::Owned => quote! {#modified_name #ty_generics },
-                    SelfType::Box => quote! { std::boxed::Box<#modified_name #ty_generics>},
-                }
-            };
             let vis = &morphed.vis;
             syn::Fields::Unnamed(syn::parse_quote! { (#vis faux::MaybeFaux<#wrapped_self>) })
         };
 
-        Mockable { real, morphed }
+        #[cfg(feature = "serde")]
+        let forwarded = serde.impls(&morphed, &wrapped_self);
+        #[cfg(not(feature = "serde"))]
+        let forwarded = proc_macro2::TokenStream::new();
+
+        Mockable {
+            real,
+            morphed,
+            forwarded,
+            wrapped_self,
+        }
     }
 }
 
 impl From<Mockable> for proc_macro::TokenStream {
     fn from(mockable: Mockable) -> Self {
-        let Mockable { real, morphed } = mockable;
+        let Mockable {
+            real,
+            morphed,
+            forwarded,
+            wrapped_self,
+        } = mockable;
         let (impl_generics, ty_generics, where_clause) = real.generics.split_for_impl();
         let name = &morphed.ident;
         let name_str = name.to_string();
@@ -61,8 +88,28 @@ impl From<Mockable> for proc_macro::TokenStream {
                 pub fn faux() -> Self {
                     Self(faux::MaybeFaux::faux(#name_str))
                 }
+
+                /// Returns the real instance, or `None` for mocks
+                #[allow(dead_code)]
+                pub fn faux_real(&self) -> Option<&#wrapped_self> {
+                    match &self.0 {
+                        faux::MaybeFaux::Real(real) => Some(real),
+                        faux::MaybeFaux::Faux(_) => None,
+                    }
+                }
+
+                /// Returns the real instance mutably, or `None` for mocks
+                #[allow(dead_code)]
+                pub fn faux_real_mut(&mut self) -> Option<&mut #wrapped_self> {
+                    match &mut self.0 {
+                        faux::MaybeFaux::Real(real) => Some(real),
+                        faux::MaybeFaux::Faux(_) => None,
+                    }
+                }
             }
 
+            #forwarded
+
             #[allow(non_camel_case_types)]
             #real
         })
@@ -0,0 +1,160 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+
+/// The serde derives found in a mockable struct
+///
+/// The derives are applied only to the real struct. The morphed
+/// struct forwards to it through `faux::MaybeFaux` instead.
+#[derive(Default)]
+pub struct Derives {
+    serialize: bool,
+    deserialize: bool,
+}
+
+impl Derives {
+    /// Removes the serde derives and helper attributes from the
+    /// morphed struct
+    ///
+    /// The real struct keeps the name of the morphed struct when
+    /// (de)serialized unless it was already renamed.
+    pub fn take(morphed: &mut syn::ItemStruct, real: &mut syn::ItemStruct) -> Self {
+        let mut derives = Derives::default();
+
+        morphed.attrs.retain(|attr| !attr.path().is_ident("serde"));
+        morphed.attrs.retain_mut(|attr| {
+            if !attr.path().is_ident("derive") {
+                return true;
+            }
+
+            let paths = match attr
+                .parse_args_with(Punctuated::<syn::Path, syn::Token![,]>::parse_terminated)
+            {
+                Ok(paths) => paths,
+                // let the compiler complain about it
+                Err(_) => return true,
+            };
+
+            let paths: Punctuated<_, syn::Token![,]> = paths
+                .into_iter()
+                .filter(|path| {
+                    let ident = &path.segments.last().unwrap().ident;
+                    if ident == "Serialize" {
+                        derives.serialize = true;
+                        false
+                    } else if ident == "Deserialize" {
+                        derives.deserialize = true;
+                        false
+                    } else {
+                        true
+                    }
+                })
+                .collect();
+
+            if paths.is_empty() {
+                return false;
+            }
+            *attr = syn::parse_quote! { #[derive(#paths)] };
+            true
+        });
+
+        let renamed = real
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("serde"))
+            .filter_map(|attr| attr.meta.require_list().ok())
+            .flat_map(|list| list.tokens.clone())
+            .any(
+                |token| matches!(token, proc_macro2::TokenTree::Ident(ident) if ident == "rename"),
+            );
+
+        if (derives.serialize || derives.deserialize) && !renamed {
+            let name = morphed.ident.to_string();
+            real.attrs
+                .push(syn::parse_quote! { #[serde(rename = #name)] });
+        }
+
+        derives
+    }
+
+    /// Creates the serde implementations for the morphed struct
+    ///
+    /// `wrapped` is the type of the real instance stored in
+    /// `faux::MaybeFaux`.
+    pub fn impls(&self, morphed: &syn::ItemStruct, wrapped: &TokenStream) -> TokenStream {
+        let name = &morphed.ident;
+        let (impl_generics, ty_generics, where_clause) = morphed.generics.split_for_impl();
+        let bounded = |bound: TokenStream| {
+            let mut where_clause = where_clause
+                .cloned()
+                .unwrap_or_else(|| syn::parse_quote! { where });
+            where_clause
+                .predicates
+                .push(syn::parse_quote! { #wrapped: #bound });
+            where_clause
+        };
+
+        let mut impls = TokenStream::new();
+
+        if self.serialize {
+            // named like the helpers of a trait method so they cannot
+            // collide with the helpers of a `serialize` method of the
+            // struct, and so `when!(<mock as Serialize>.serialize)`
+            // finds them
+            let namespace = syn::Ident::new("Serialize", proc_macro2::Span::call_site());
+            let serialize = syn::Ident::new("serialize", proc_macro2::Span::call_site());
+            let when_ident = crate::methods::helper_ident("_when", Some(&namespace), &serialize);
+            let faux_ident = crate::methods::helper_ident("_faux", Some(&namespace), &serialize);
+            let serialize_where = bounded(quote! { faux::serde::Serialize });
+            impls.extend(quote! {
+                impl #impl_generics faux::serde::Serialize for #name #ty_generics #serialize_where {
+                    fn serialize<__S: faux::serde::Serializer>(&self, serializer: __S) -> std::result::Result<__S::Ok, __S::Error> {
+                        match &self.0 {
+                            faux::MaybeFaux::Faux(_maybe_faux_faux) => {
+                                // mocks have no data so they serialize a stubbed substitute
+                                let substitute: Self = faux::serialize_stub(_maybe_faux_faux, <Self>::#faux_ident)?;
+                                faux::serde::Serialize::serialize(&substitute.0, serializer)
+                            }
+                            real => faux::serde::Serialize::serialize(real, serializer),
+                        }
+                    }
+                }
+
+                impl #impl_generics #name #ty_generics #where_clause {
+                    #[allow(non_snake_case)]
+                    pub fn #when_ident<'m>(&'m mut self) -> faux::When<'m, &'m Self, (), Self, faux::matcher::AnyInvocation> {
+                        match &mut self.0 {
+                            faux::MaybeFaux::Faux(_maybe_faux_faux) => faux::When::new(
+                                <Self>::#faux_ident,
+                                "serialize",
+                                _maybe_faux_faux
+                            ),
+                            faux::MaybeFaux::Real(_) => panic!("not allowed to stub a real instance!"),
+                        }
+                    }
+
+                    #[allow(clippy::needless_arbitrary_self_type, non_snake_case)]
+                    pub fn #faux_ident<'m>(self: &'m Self, _: ()) -> Self {
+                        panic!("do not call this (<Self>::_faux_Serialize_serialize){:?}", <Self>::#faux_ident as *const ())
+                    }
+                }
+            });
+        }
+
+        if self.deserialize {
+            let mut de_generics = morphed.generics.clone();
+            de_generics.params.insert(0, syn::parse_quote! { '__de });
+            let (de_impl_generics, _, _) = de_generics.split_for_impl();
+            let deserialize_where = bounded(quote! { faux::serde::Deserialize<'__de> });
+            impls.extend(quote! {
+                impl #de_impl_generics faux::serde::Deserialize<'__de> for #name #ty_generics #deserialize_where {
+                    fn deserialize<__D: faux::serde::Deserializer<'__de>>(deserializer: __D) -> std::result::Result<Self, __D::Error> {
+                        <faux::MaybeFaux<#wrapped> as faux::serde::Deserialize<'__de>>::deserialize(deserializer).map(Self)
+                    }
+                }
+            });
+        }
+
+        impls
+    }
+}
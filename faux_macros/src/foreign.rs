@@ -0,0 +1,219 @@
+use crate::{create, methods};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+
+/// A struct from another crate and the methods to mock from it
+///
+/// ```ignore
+/// pub struct sdk::Client;
+/// impl {
+///     fn get(&self, key: &str) -> Result<Vec<u8>, sdk::Error>;
+/// }
+/// impl sdk::Named {
+///     fn name(&self) -> String;
+/// }
+/// ```
+pub struct Foreign {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    path: syn::Path,
+    impls: Vec<ForeignImpl>,
+}
+
+struct ForeignImpl {
+    trait_: Option<syn::Path>,
+    methods: Vec<syn::TraitItemFn>,
+}
+
+impl Parse for Foreign {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<syn::Token![struct]>()?;
+        let path = input.parse()?;
+        input.parse::<syn::Token![;]>()?;
+
+        let mut impls = vec![];
+        while !input.is_empty() {
+            impls.push(input.parse()?);
+        }
+
+        Ok(Foreign {
+            attrs,
+            vis,
+            path,
+            impls,
+        })
+    }
+}
+
+impl Parse for ForeignImpl {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<syn::Token![impl]>()?;
+        let trait_ = if input.peek(syn::token::Brace) {
+            None
+        } else {
+            Some(input.parse()?)
+        };
+
+        let content;
+        syn::braced!(content in input);
+        let mut methods = vec![];
+        while !content.is_empty() {
+            let method: syn::TraitItemFn = content.parse()?;
+            if let Some(block) = &method.default {
+                return Err(syn::Error::new_spanned(
+                    block,
+                    "faux::mock_foreign! methods must not have a body; they call the foreign method",
+                ));
+            }
+            methods.push(method);
+        }
+
+        Ok(ForeignImpl { trait_, methods })
+    }
+}
+
+impl Foreign {
+    pub fn expand(self) -> darling::Result<TokenStream> {
+        let Foreign {
+            attrs,
+            vis,
+            path,
+            impls,
+        } = self;
+
+        let ident = match path.segments.last() {
+            Some(segment) if segment.arguments.is_none() => segment.ident.clone(),
+            _ => {
+                return Err(darling::Error::custom(
+                    "faux::mock_foreign! only supports paths to non-generic structs",
+                )
+                .with_span(&path))
+            }
+        };
+
+        let original: syn::ItemStruct = syn::parse_quote! {
+            #(#attrs)*
+            #vis struct #ident(#path);
+        };
+        let mockable = create::Mockable::new(original, create::Args::default());
+        let mut expanded = TokenStream::from(proc_macro::TokenStream::from(mockable));
+
+        for ForeignImpl { trait_, methods } in impls {
+            let methods = methods
+                .into_iter()
+                .map(|method| delegate(method, &path, trait_.as_ref()))
+                .collect::<darling::Result<Vec<_>>>()?;
+
+            let original: syn::ItemImpl = match &trait_ {
+                None => syn::parse_quote! { impl #ident { #(#methods)* } },
+                Some(trait_) => syn::parse_quote! { impl #trait_ for #ident { #(#methods)* } },
+            };
+            let mockable = methods::Mockable::new(original, methods::Args::default())?;
+            expanded.extend(TokenStream::from(proc_macro::TokenStream::from(mockable)));
+        }
+
+        let real_ident = create::real_struct_new_ident(&ident);
+        expanded.extend(quote! {
+            impl std::convert::From<#path> for #ident {
+                fn from(foreign: #path) -> Self {
+                    Self(faux::MaybeFaux::Real(#real_ident(foreign)))
+                }
+            }
+        });
+
+        Ok(expanded)
+    }
+}
+
+// creates a method that calls the foreign method of the same name
+fn delegate(
+    method: syn::TraitItemFn,
+    path: &syn::Path,
+    trait_: Option<&syn::Path>,
+) -> darling::Result<syn::ImplItemFn> {
+    let syn::TraitItemFn { attrs, mut sig, .. } = method;
+
+    let mut args = vec![];
+    for (i, arg) in sig.inputs.iter_mut().enumerate() {
+        match arg {
+            syn::FnArg::Receiver(receiver) => {
+                if receiver.colon_token.is_some() {
+                    return Err(darling::Error::custom(
+                        "faux::mock_foreign! only supports `self`, `&self`, and `&mut self` receivers",
+                    )
+                    .with_span(receiver));
+                }
+                args.push(match (&receiver.reference, &receiver.mutability) {
+                    (None, _) => quote! { self.0 },
+                    (Some(_), None) => quote! { &self.0 },
+                    (Some(_), Some(_)) => quote! { &mut self.0 },
+                });
+            }
+            syn::FnArg::Typed(arg) => match arg.pat.as_ref() {
+                syn::Pat::Ident(pat_ident) => {
+                    let ident = &pat_ident.ident;
+                    args.push(quote! { #ident });
+                }
+                _ => {
+                    let ident = quote::format_ident!("_faux_arg_{}", i);
+                    *arg.pat = syn::parse_quote! { #ident };
+                    args.push(quote! { #ident });
+                }
+            },
+        }
+    }
+
+    let name = &sig.ident;
+    let mut call = match trait_ {
+        None => quote! { #path::#name(#(#args),*) },
+        Some(trait_) => quote! { <#path as #trait_>::#name(#(#args),*) },
+    };
+    if sig.asyncness.is_some() {
+        call = quote! { #call.await };
+    }
+
+    // foreign instances are wrapped back into `Self`
+    let body = match &sig.output {
+        syn::ReturnType::Type(_, ty) => match ty.as_ref() {
+            syn::Type::Path(ty) if ty.qself.is_none() && ty.path.is_ident("Self") => {
+                quote! { Self(#call) }
+            }
+            syn::Type::Path(ty) if ty.qself.is_none() && wraps_self(&ty.path) => {
+                quote! { #call.map(Self) }
+            }
+            _ => call,
+        },
+        syn::ReturnType::Default => call,
+    };
+
+    let vis = match trait_ {
+        None => quote! { pub },
+        Some(_) => quote! {},
+    };
+
+    Ok(syn::parse_quote! {
+        #(#attrs)*
+        #vis #sig {
+            #body
+        }
+    })
+}
+
+// whether the type is an `Option<Self>` or a `Result<Self, E>`
+fn wraps_self(path: &syn::Path) -> bool {
+    let segment = path.segments.last().unwrap();
+    if segment.ident != "Option" && segment.ident != "Result" {
+        return false;
+    }
+
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => matches!(
+            args.args.first(),
+            Some(syn::GenericArgument::Type(syn::Type::Path(ty))) if ty.path.is_ident("Self")
+        ),
+        _ => false,
+    }
+}
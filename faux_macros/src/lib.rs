@@ -1,6 +1,8 @@
 extern crate proc_macro;
 
+mod cfg;
 mod create;
+mod foreign;
 mod methods;
 mod self_type;
 
@@ -12,7 +14,7 @@ use quote::quote;
 pub fn create(args: TokenStream, original: TokenStream) -> TokenStream {
     let original = syn::parse_macro_input!(original as syn::ItemStruct);
 
-    let args = match NestedMeta::parse_meta_list(args.into())
+    let mut args = match NestedMeta::parse_meta_list(args.into())
         .map_err(darling::Error::from)
         .and_then(|v| create::Args::from_list(&v))
     {
@@ -20,16 +22,20 @@ pub fn create(args: TokenStream, original: TokenStream) -> TokenStream {
         Err(e) => return e.write_errors().into(),
     };
 
-    let mockable = create::Mockable::new(original, args);
-
-    TokenStream::from(mockable)
+    match args.cfg.take() {
+        None => TokenStream::from(create::Mockable::new(original, args)),
+        Some(cfg) => {
+            let mockable = TokenStream::from(create::Mockable::new(original.clone(), args));
+            cfg.gate(mockable.into(), original).into()
+        }
+    }
 }
 
 #[proc_macro_attribute]
 pub fn methods(args: TokenStream, original: TokenStream) -> TokenStream {
     let original = syn::parse_macro_input!(original as syn::ItemImpl);
 
-    let args = match NestedMeta::parse_meta_list(args.into())
+    let mut args = match NestedMeta::parse_meta_list(args.into())
         .map_err(darling::Error::from)
         .and_then(|v| methods::Args::from_list(&v))
     {
@@ -37,22 +43,115 @@ pub fn methods(args: TokenStream, original: TokenStream) -> TokenStream {
         Err(e) => return e.write_errors().into(),
     };
 
-    match methods::Mockable::new(original, args) {
-        Ok(mockable) => TokenStream::from(mockable),
+    match args.cfg.take() {
+        None => match methods::Mockable::new(original, args) {
+            Ok(mockable) => TokenStream::from(mockable),
+            Err(e) => e.write_errors().into(),
+        },
+        Some(cfg) => match methods::Mockable::new(original.clone(), args) {
+            Ok(mockable) => cfg
+                .gate(TokenStream::from(mockable).into(), original)
+                .into(),
+            Err(e) => e.write_errors().into(),
+        },
+    }
+}
+
+#[proc_macro_attribute]
+pub fn real(args: TokenStream, original: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        return darling::Error::custom("#[faux::real] does not take arguments")
+            .write_errors()
+            .into();
+    }
+
+    // only a marker for #[faux::methods]; do nothing if used without it
+    original
+}
+
+#[proc_macro]
+pub fn mock_foreign(input: TokenStream) -> TokenStream {
+    let foreign = syn::parse_macro_input!(input as foreign::Foreign);
+
+    match foreign.expand() {
+        Ok(expanded) => expanded.into(),
         Err(e) => e.write_errors().into(),
     }
 }
 
 #[proc_macro]
 pub fn when(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    match syn::parse_macro_input!(input as syn::Expr) {
+    let WhenInput { trait_, expr } = syn::parse_macro_input!(input as WhenInput);
+
+    match accessor("_when", trait_.as_ref(), expr, false) {
+        Ok(when) => TokenStream::from(quote!({ #when })),
+        Err(e) => e.write_errors().into(),
+    }
+}
+
+#[proc_macro]
+pub fn wait_for(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let WaitForInput {
+        asyncness,
+        when: WhenInput { trait_, expr },
+        times,
+        timeout,
+    } = syn::parse_macro_input!(input as WaitForInput);
+
+    // calls before the wait can only be counted if every call matches
+    let wait = match accessor("_wait", trait_.as_ref(), expr, true) {
+        Ok(wait) => wait,
+        Err(e) => return e.write_errors().into(),
+    };
+    let times = times.map(|times| quote! { .times(#times) });
+    let timeout = timeout.map(|timeout| quote! { .timeout(#timeout) });
+    let block = match asyncness {
+        None => quote! { .wait() },
+        Some(_) => quote! { .wait_async() },
+    };
+
+    TokenStream::from(quote!({ #wait #times #timeout #block }))
+}
+
+#[proc_macro]
+pub fn calls(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let WhenInput { trait_, expr } = syn::parse_macro_input!(input as WhenInput);
+
+    // the arguments of previous calls are not kept around to match
+    if let syn::Expr::MethodCall(call) = &expr {
+        if !call.args.is_empty() {
+            return darling::Error::custom("faux::calls! does not accept arguments")
+                .with_span(&call.args)
+                .write_errors()
+                .into();
+        }
+    }
+
+    match accessor("_calls", trait_.as_ref(), expr, false) {
+        Ok(calls) => TokenStream::from(quote!({ #calls })),
+        Err(e) => e.write_errors().into(),
+    }
+}
+
+// expands `receiver.method(args)` into a call to the generated
+// accessor, e.g., `receiver._when_method().with_args(matchers)`
+fn accessor(
+    prefix: &str,
+    trait_: Option<&syn::Path>,
+    expr: syn::Expr,
+    skip_any: bool,
+) -> darling::Result<proc_macro2::TokenStream> {
+    let namespace = trait_.map(|t| methods::trait_namespace(t, &syn::Generics::default()));
+    let namespace = namespace.as_ref();
+
+    match expr {
         syn::Expr::Field(syn::ExprField {
             base,
             member: syn::Member::Named(ident),
             ..
         }) => {
-            let when = quote::format_ident!("_when_{}", ident);
-            TokenStream::from(quote!( { #base.#when() }))
+            let accessor = methods::helper_ident(prefix, namespace, &ident);
+            Ok(quote! { #base.#accessor() })
         }
         syn::Expr::MethodCall(syn::ExprMethodCall {
             receiver,
@@ -61,28 +160,124 @@ pub fn when(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             turbofish,
             ..
         }) => {
-            let when = quote::format_ident!("_when_{}", method);
+            let accessor = methods::helper_ident(prefix, namespace, &method);
+            let all_any = args.iter().all(|arg| matches!(arg, syn::Expr::Infer(_)));
 
             let args = args
                 .into_iter()
                 .map(expr_to_matcher)
-                .collect::<Result<Vec<_>, _>>();
+                .collect::<Result<Vec<_>, _>>()?;
 
-            match args {
-                Err(e) => e.write_errors().into(),
-                Ok(args) if args.is_empty() => { TokenStream::from(quote!({ #receiver.#when #turbofish() }))}
-                Ok(args) => { TokenStream::from(quote!({ #receiver.#when #turbofish().with_args((#(#args,)*)) }))}
+            if args.is_empty() || (skip_any && all_any) {
+                Ok(quote! { #receiver.#accessor #turbofish() })
+            } else {
+                Ok(quote! { #receiver.#accessor #turbofish().with_args((#(#args,)*)) })
             }
         }
-        expr => darling::Error::custom("faux::when! only accepts arguments in the format of: `when!(receiver.method)` or `receiver.method(args...)`")
-             .with_span(&expr)
-             .write_errors()
-             .into(),
+        expr => Err(darling::Error::custom("faux::when! only accepts arguments in the format of: `when!(receiver.method)` or `receiver.method(args...)`")
+             .with_span(&expr)),
     }
 }
 
 use quote::ToTokens;
 
+/// The input to `when!`: either `receiver.method(args)` or
+/// `<receiver as Trait>.method(args)`
+struct WhenInput {
+    trait_: Option<syn::Path>,
+    expr: syn::Expr,
+}
+
+impl syn::parse::Parse for WhenInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if !input.peek(syn::Token![<]) {
+            return Ok(WhenInput {
+                trait_: None,
+                expr: input.parse()?,
+            });
+        }
+
+        input.parse::<syn::Token![<]>()?;
+        // `receiver as Trait` would otherwise be parsed as a cast
+        let mut receiver = proc_macro2::TokenStream::new();
+        while !input.peek(syn::Token![as]) {
+            if input.is_empty() {
+                return Err(input.error("expected `<receiver as Trait>`"));
+            }
+            receiver.extend(std::iter::once(input.parse::<proc_macro2::TokenTree>()?));
+        }
+        input.parse::<syn::Token![as]>()?;
+        let trait_ = input.parse()?;
+        input.parse::<syn::Token![>]>()?;
+
+        // stop at a trailing comma, e.g., for `wait_for!` arguments
+        let mut rest = proc_macro2::TokenStream::new();
+        while !input.is_empty() && !input.peek(syn::Token![,]) {
+            rest.extend(std::iter::once(input.parse::<proc_macro2::TokenTree>()?));
+        }
+        let mut expr: syn::Expr = syn::parse2(quote! { _faux_receiver #rest })?;
+        let receiver = syn::parse2(receiver)?;
+        match &mut expr {
+            syn::Expr::Field(field) => *field.base = receiver,
+            syn::Expr::MethodCall(call) => *call.receiver = receiver,
+            _ => {}
+        }
+
+        Ok(WhenInput {
+            trait_: Some(trait_),
+            expr,
+        })
+    }
+}
+
+/// The input to `wait_for!`: `[async] receiver.method(args)`
+/// followed by optional `times = ...` and `timeout = ...` arguments
+struct WaitForInput {
+    asyncness: Option<syn::Token![async]>,
+    when: WhenInput,
+    times: Option<syn::Expr>,
+    timeout: Option<syn::Expr>,
+}
+
+impl syn::parse::Parse for WaitForInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let asyncness = input.parse()?;
+        let when = input.parse()?;
+        let mut times = None;
+        let mut timeout = None;
+
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name: syn::Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            let value = input.parse()?;
+            let slot = match name.to_string().as_str() {
+                "times" => &mut times,
+                "timeout" => &mut timeout,
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "faux::wait_for! only accepts `times = ...` and `timeout = ...`",
+                    ))
+                }
+            };
+            if slot.replace(value).is_some() {
+                return Err(syn::Error::new(name.span(), "duplicate argument"));
+            }
+        }
+
+        Ok(WaitForInput {
+            asyncness,
+            when,
+            times,
+            timeout,
+        })
+    }
+}
+
 fn ref_matcher_maybe(
     expr: &syn::Expr,
     left: &syn::Expr,
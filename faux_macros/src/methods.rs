@@ -1,30 +1,57 @@
 mod morphed;
 mod receiver;
 
-use crate::{create, self_type::SelfType};
+use crate::{cfg::Cfg, create, self_type::SelfType};
 use darling::FromMeta;
 use morphed::Signature;
-use quote::quote;
-use syn::PathArguments;
+
+pub use morphed::{helper_ident, trait_namespace};
+use quote::{quote, ToTokens};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+use syn::{visit_mut::VisitMut, PathArguments};
 
 #[derive(Default, FromMeta)]
 #[darling(default)]
 pub struct Args {
     path: Option<syn::Path>,
     self_type: SelfType,
+    unstubbed: Fallback,
+    unmatched: Fallback,
+    pub cfg: Option<Cfg>,
+}
+
+/// What a mocked method does with a call it cannot handle, either
+/// because it was never stubbed or because none of its stubs matched
+#[derive(FromMeta, PartialEq, Eq, Copy, Clone, Default)]
+#[darling(rename_all = "lowercase")]
+pub enum Fallback {
+    #[default]
+    Panic,
+    /// return an `Err` for methods that return a `Result` whose error
+    /// implements `From<faux::Unstubbed>` (never stubbed) or
+    /// `From<faux::InvocationError>` (no stub matched)
+    Err,
 }
 
 pub struct Mockable {
     // the real definitions inside the impl block
     real: syn::ItemImpl,
-    // the morphed definitions
-    morphed: syn::ItemImpl,
-    // the _when_ methods in their own impl
-    whens: syn::ItemImpl,
+    // the morphed definitions; `None` for impls that only apply to
+    // real instances
+    morphed: Option<syn::ItemImpl>,
+    // the _when_, _wait_ and _calls_ methods in their own impl
+    whens: Option<syn::ItemImpl>,
+    // the _when_, _wait_ and _calls_ methods of trait methods without
+    // the trait's name, in a private trait so they cannot clash with
+    // the helpers of other methods of the same name
+    bare_whens: proc_macro2::TokenStream,
     // path to real struct
     real_ty: syn::TypePath,
-    // path to morphed struct
-    morphed_ty: syn::TypePath,
+    // path to morphed struct as used in the real impl
+    alias_ty: syn::TypePath,
 }
 
 impl Mockable {
@@ -37,39 +64,103 @@ impl Mockable {
             }
         };
 
-        if let Some(segment) = morphed_ty
+        // impls of `crate::path::to::Type` or `super::Type` cannot
+        // be nested in mods to find the real struct so the real impl
+        // uses the type's name instead and the alias takes the path
+        let is_rooted = morphed_ty
             .path
             .segments
             .iter()
-            .find(|segment| segment.ident == "crate" || segment.ident == "super")
-        {
-            return Err(
-                darling::Error::custom(
-                    format!("#[faux::methods] does not support implementing types with '{segment}' in the path. Consider importing one level past '{segment}' and using #[faux::methods(path = \"{segment}\")]", segment = segment.ident)
-                ).with_span(segment)
-            );
+            .any(|segment| segment.ident == "crate" || segment.ident == "super");
+        if is_rooted {
+            if let Some(path) = &args.path {
+                return Err(darling::Error::custom(
+                    "#[faux::methods] does not support the 'path' argument for implementing types with 'crate' or 'super' in the path",
+                )
+                .with_span(path));
+            }
         }
 
         // start transforming
         let real_ty = real_ty(&morphed_ty, args.path);
 
         let mut morphed = real.clone();
+        let mut real = real;
+        for item in &mut real.items {
+            if let syn::ImplItem::Fn(m) = item {
+                // the real implementation is only called by other real methods
+                if take_real_attr(&mut m.attrs) {
+                    m.attrs.push(syn::parse_quote! { #[allow(dead_code)] });
+                }
+            }
+        }
+        let alias_ty = if is_rooted {
+            RootedPaths(&morphed_ty.path).visit_item_impl_mut(&mut real);
+            syn::TypePath {
+                qself: None,
+                path: morphed_ty.path.segments.last().unwrap().clone().into(),
+            }
+        } else {
+            morphed_ty.clone()
+        };
+        let real = real;
+
+        // mocks have nothing to clean up so only the real struct is
+        // dropped with the user's logic. A `Drop` impl on the morphed
+        // struct would also forbid moving the real instance out of it
+        if is_drop(&real) {
+            return Ok(Mockable {
+                real,
+                morphed: None,
+                whens: None,
+                bare_whens: quote! {},
+                real_ty,
+                alias_ty,
+            });
+        }
 
         let mut methods = morphed.items.iter_mut().filter_map(|item| match item {
             syn::ImplItem::Fn(m) => Some(m),
             _ => None,
         });
 
+        let namespace = real
+            .trait_
+            .as_ref()
+            .map(|(_, path, _)| morphed::trait_namespace(path, &real.generics));
+
         let mut when_methods = vec![];
+        let mut bare_whens = vec![];
         for func in &mut methods {
+            // #[faux::real] methods keep their body so they run on mocks too
+            if take_real_attr(&mut func.attrs) {
+                continue;
+            }
             normalize_idents(&mut func.sig);
             let signature = Signature::morph(
                 &func.sig,
                 real.trait_.as_ref().map(|(_, path, _)| path),
+                namespace.clone(),
                 &func.vis,
             );
-            func.block = signature.create_body(args.self_type, &real_ty, &morphed_ty)?;
+            func.block = signature.create_body(
+                args.self_type,
+                args.unstubbed,
+                args.unmatched,
+                &real_ty,
+                &morphed_ty,
+            )?;
             if let Some(methods) = signature.create_when() {
+                if real.trait_.is_some() {
+                    // the first methods are the `_when_`, `_wait_` and
+                    // `_calls_` accessors
+                    let bare = [
+                        bare_when("_when", &methods[0], signature.name()),
+                        bare_when("_wait", &methods[1], signature.name()),
+                        bare_when("_calls", &methods[2], signature.name()),
+                    ];
+                    bare_whens.extend(bare);
+                }
                 when_methods.extend(methods.into_iter().map(syn::ImplItem::Fn));
             }
         }
@@ -108,7 +199,7 @@ impl Mockable {
             _ => syn::Generics::default(),
         };
 
-        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
         let whens = syn::parse_quote! {
             impl #impl_generics #morphed_ty #where_clause {
@@ -116,12 +207,40 @@ impl Mockable {
             }
         };
 
+        // trait methods may also be stubbed without naming the trait
+        // when the generated trait is in scope
+        let bare_whens = match &real.trait_ {
+            Some((_, trait_, _)) if !bare_whens.is_empty() => {
+                let trait_ident = quote::format_ident!(
+                    "_FauxWhen_{}_{}_{:016x}",
+                    morphed_ty.path.segments.last().unwrap().ident,
+                    trait_.segments.last().unwrap().ident,
+                    impl_hash(&real)
+                );
+                let (sigs, methods): (Vec<_>, Vec<_>) = bare_whens.into_iter().unzip();
+                quote! {
+                    #[doc(hidden)]
+                    #[allow(non_camel_case_types)]
+                    #[allow(clippy::mut_from_ref)]
+                    trait #trait_ident #impl_generics: Sized #where_clause {
+                        #(#sigs;)*
+                    }
+
+                    impl #impl_generics #trait_ident #ty_generics for #morphed_ty #where_clause {
+                        #(#methods)*
+                    }
+                }
+            }
+            _ => quote! {},
+        };
+
         Ok(Mockable {
             real,
-            morphed,
-            whens,
+            morphed: Some(morphed),
+            whens: Some(whens),
+            bare_whens,
             real_ty,
-            morphed_ty,
+            alias_ty,
         })
     }
 }
@@ -132,23 +251,27 @@ impl From<Mockable> for proc_macro::TokenStream {
             real,
             morphed,
             whens,
+            bare_whens,
             mut real_ty,
-            morphed_ty,
+            alias_ty,
         } = mockable;
 
         // create an identifier for the mod containing the real implementation
         // this is necessary until we are allowed to introduce type aliases within impl blocks
+        // the name is derived from the impl block so that the expansion
+        // is the same on every build. Blocks for the same struct in the
+        // same module differ in their methods so their names differ too
         let mod_ident = {
-            let uuid = uuid::Uuid::new_v4();
+            let hash = impl_hash(&real);
             let ident = &real_ty.path.segments.last().unwrap().ident;
             syn::Ident::new(
                 &match &real.trait_ {
-                    None => format!("_faux_real_impl_{}_{}", ident, uuid.simple()),
+                    None => format!("_faux_real_impl_{}_{:016x}", ident, hash),
                     Some((_, trait_, _)) => format!(
-                        "_faux_real_impl_{}_{}_{}",
+                        "_faux_real_impl_{}_{}_{:016x}",
                         ident,
                         trait_.segments.last().unwrap().ident,
-                        uuid.simple()
+                        hash
                     ),
                 },
                 proc_macro2::Span::call_site(),
@@ -165,7 +288,7 @@ impl From<Mockable> for proc_macro::TokenStream {
 
         // creates an alias `type Foo = path::to::RealFoo` that may be wrapped inside some mods
         let alias = {
-            let mut path_to_ty = morphed_ty.path.segments;
+            let mut path_to_ty = alias_ty.path.segments;
             let path_to_real_from_alias_mod = {
                 // let mut real_ty = real_ty.clone();
                 real_ty.path.segments.last_mut().unwrap().arguments = PathArguments::None;
@@ -211,6 +334,8 @@ impl From<Mockable> for proc_macro::TokenStream {
 
             #whens
 
+            #bare_whens
+
             mod #mod_ident {
                 // make everything that was in-scope above also in-scope in this mod
                 use super::*;
@@ -223,6 +348,40 @@ impl From<Mockable> for proc_macro::TokenStream {
     }
 }
 
+// whether the impl block is `impl Drop for Type`
+fn is_drop(real: &syn::ItemImpl) -> bool {
+    match &real.trait_ {
+        Some((None, trait_, _)) => trait_.segments.last().unwrap().ident == "Drop",
+        _ => false,
+    }
+}
+
+// a hash of the impl block that is the same on every build
+fn impl_hash(real: &syn::ItemImpl) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    real.to_token_stream().to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+// creates the signature and implementation of an accessor, e.g.,
+// `_when_{name}`, that calls its namespaced version, e.g.,
+// `_when_{Trait}_{name}`
+fn bare_when(
+    prefix: &str,
+    namespaced: &syn::ImplItemFn,
+    name: &syn::Ident,
+) -> (syn::Signature, syn::ImplItemFn) {
+    let mut sig = namespaced.sig.clone();
+    sig.ident = morphed::helper_ident(prefix, None, name);
+    let namespaced = &namespaced.sig.ident;
+    let method = syn::parse_quote! {
+        #sig {
+            self.#namespaced()
+        }
+    };
+    (sig, method)
+}
+
 fn real_ty(morphed_ty: &syn::TypePath, path: Option<syn::Path>) -> syn::TypePath {
     let type_ident = &morphed_ty.path.segments.last().unwrap().ident;
     // combine a passed in path if given one
@@ -247,6 +406,28 @@ fn real_ty(morphed_ty: &syn::TypePath, path: Option<syn::Path>) -> syn::TypePath
     path_to_morph_from_here
 }
 
+// replaces `crate::path::to::Type` with `Type` so it refers to the alias
+struct RootedPaths<'p>(&'p syn::Path);
+
+impl VisitMut for RootedPaths<'_> {
+    fn visit_path_mut(&mut self, path: &mut syn::Path) {
+        let len = self.0.segments.len();
+        let is_rooted = path.leading_colon.is_none()
+            && path.segments.len() >= len
+            && path
+                .segments
+                .iter()
+                .zip(&self.0.segments)
+                .all(|(a, b)| a.ident == b.ident);
+
+        if is_rooted {
+            path.segments = path.segments.clone().into_iter().skip(len - 1).collect();
+        }
+
+        syn::visit_mut::visit_path_mut(self, path);
+    }
+}
+
 // makes methods in this impl block be at least visible to super
 fn publicize_methods(impl_block: &mut syn::ItemImpl) {
     impl_block
@@ -260,6 +441,21 @@ fn publicize_methods(impl_block: &mut syn::ItemImpl) {
         .for_each(|method| method.vis = syn::parse_quote! { pub(super) });
 }
 
+// removes the #[faux::real] attribute, returning whether it was present
+//
+// only the full path is matched as a bare `#[real]` may belong to
+// another crate
+fn take_real_attr(attrs: &mut Vec<syn::Attribute>) -> bool {
+    let is_real = |attr: &syn::Attribute| {
+        let segments = &attr.path().segments;
+        segments.len() == 2 && segments[0].ident == "faux" && segments[1].ident == "real"
+    };
+
+    let len = attrs.len();
+    attrs.retain(|attr| !is_real(attr));
+    attrs.len() != len
+}
+
 fn normalize_idents(signature: &mut syn::Signature) {
     signature
         .inputs
@@ -1,25 +1,50 @@
-use crate::{methods::receiver::Receiver, self_type::SelfType};
+use crate::{
+    methods::{receiver::Receiver, Fallback},
+    self_type::SelfType,
+};
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
 use syn::{
-    spanned::Spanned, GenericArgument, Generics, Ident, Lifetime, PathArguments, PathSegment, Type,
-    TypePath,
+    spanned::Spanned,
+    visit_mut::{self, VisitMut},
+    GenericArgument, Generics, Ident, Lifetime, PathArguments, PathSegment, Type, TypePath,
 };
 
 pub struct Signature<'a> {
     name: &'a syn::Ident,
     args: Vec<&'a syn::Pat>,
     is_async: bool,
+    // how the future is returned when not an `async fn`
+    returned_future: Option<ReturnedFuture>,
     output: Option<&'a syn::Type>,
     method_data: Option<MethodData<'a>>,
     trait_path: Option<&'a syn::Path>,
+    // helpers of trait methods are namespaced by the trait so they do
+    // not clash with methods of the same name in other impls
+    namespace: Option<syn::Ident>,
 }
 
 pub struct MethodData<'a> {
     receiver: Receiver,
     generics: syn::Generics,
     arg_types: Vec<WhenArg<'a>>,
-    is_private: bool,
+    // visibility of the generated `_when_`, `_wait_` and `_calls_`
+    // accessors
+    vis: syn::Visibility,
+}
+
+/// A future returned by a method that is stubbed as an `async fn`
+#[derive(Clone, Copy)]
+enum ReturnedFuture {
+    /// `impl Future<Output = T>`
+    Impl,
+    /// `Pin<Box<dyn Future<Output = T>>>` or `BoxFuture<'_, T>`, e.g.,
+    /// as expanded by `async-trait`
+    Boxed,
 }
 
 #[derive(Debug)]
@@ -90,6 +115,7 @@ impl<'a> Signature<'a> {
     pub fn morph(
         signature: &'a syn::Signature,
         trait_path: Option<&'a syn::Path>,
+        namespace: Option<syn::Ident>,
         vis: &syn::Visibility,
     ) -> Signature<'a> {
         let receiver = Receiver::from_signature(signature);
@@ -100,6 +126,12 @@ impl<'a> Signature<'a> {
             syn::ReturnType::Type(_, ty) => Some(ty.as_ref()),
         };
 
+        // methods that return `impl Future` or a boxed future are
+        // stubbed as if they were `async fn`s
+        let returned_future = output.and_then(returned_future);
+        let output = returned_future.map(|(_, output)| output).or(output);
+        let returned_future = returned_future.map(|(future, _)| future);
+
         let method_data = receiver.map(|receiver| {
             let arg_types = signature
                 .inputs
@@ -117,7 +149,11 @@ impl<'a> Signature<'a> {
                 receiver,
                 generics,
                 arg_types,
-                is_private: trait_path.is_none() && *vis == syn::Visibility::Inherited,
+                // trait methods are as visible as the trait
+                vis: match trait_path {
+                    None => vis.clone(),
+                    Some(_) => syn::parse_quote! { pub },
+                },
             }
         });
 
@@ -134,16 +170,20 @@ impl<'a> Signature<'a> {
                     }
                 })
                 .collect(),
-            is_async: signature.asyncness.is_some(),
+            is_async: signature.asyncness.is_some() || returned_future.is_some(),
+            returned_future,
             output,
             method_data,
             trait_path,
+            namespace,
         }
     }
 
     pub fn create_body(
         &self,
         real_self: SelfType,
+        unstubbed: Fallback,
+        unmatched: Fallback,
         real_ty: &syn::TypePath,
         morphed_ty: &syn::TypePath,
     ) -> darling::Result<syn::Block> {
@@ -176,86 +216,176 @@ impl<'a> Signature<'a> {
         let real_self_arg = real_self_arg.as_ref();
 
         let proxy_args = real_self_arg.iter().chain(args);
-        let mut proxy_real = quote! { #proxy(#(#proxy_args),*) };
-        if self.is_async {
-            proxy_real.extend(quote! { .await })
-        }
+        let proxy_real = quote! { #proxy(#(#proxy_args),*) };
+
+        // methods that return a future get the real future or the
+        // stub when called and only await it in the returned future
+        // so it does not need to borrow `self`
+        let mut real_output = match self.returned_future {
+            Some(_) => quote! { _maybe_faux_future.await },
+            None if self.is_async => quote! { #proxy_real.await },
+            None => proxy_real.clone(),
+        };
 
+        let mut wraps_self = false;
         if let Some(output) = self.output {
-            if let Some(wrapped_self) = Self::wrap_self(output, morphed_ty, real_self, &proxy_real)?
+            if let Some(wrapped_self) =
+                Self::wrap_self(output, morphed_ty, real_self, &real_output)?
             {
-                proxy_real = wrapped_self;
+                real_output = wrapped_self;
+                wraps_self = true;
             }
         }
 
-        let ret = match &self.method_data {
+        // the real boxed future is returned as is unless its output
+        // has to be wrapped
+        let real_boxed = matches!(self.returned_future, Some(ReturnedFuture::Boxed)) && !wraps_self;
+
+        let into_future = match self.returned_future {
+            None | Some(ReturnedFuture::Impl) => quote! {},
+            Some(ReturnedFuture::Boxed) => quote! { std::boxed::Box::pin },
+        };
+
+        let block = match &self.method_data {
             // not stubbable
             // proxy to real associated function
-            None => syn::parse2(proxy_real).unwrap(),
+            None if real_boxed => syn::parse_quote! {{ #proxy_real }},
+            None if self.returned_future.is_some() => syn::parse_quote! {{
+                let _maybe_faux_future = #proxy_real;
+                #into_future(async move { #real_output })
+            }},
+            None => syn::parse_quote! {{ #real_output }},
             // else we can either proxy for real instances
             // or call the mock store for faux instances
             Some(method_data) => {
-                let call_stub = if method_data.is_private {
-                    quote! { panic!("faux error: private methods are not stubbable; and therefore not directly callable in a mock") }
+                let faux_ident = helper_ident("_faux", self.namespace(), name);
+
+                let mut args = args
+                    .iter()
+                    .zip(method_data.arg_types.iter())
+                    .map(|(ident, ty)| {
+                        if has_impl_trait(ty.0) {
+                            quote! {
+                                std::boxed::Box::new(#ident)
+                            }
+                        } else {
+                            quote! { #ident }
+                        }
+                    });
+
+                let args = if args.len() == 1 {
+                    let arg = args.next().unwrap();
+                    quote! { #arg }
+                } else {
+                    quote! { (#(#args,)*) }
+                };
+
+                let fn_name = name.to_string();
+                let mut generics_str = generic_idents
+                    .into_iter()
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                generics_str.retain(|c| !c.is_whitespace());
+
+                let unmatched = if unstubbed == Fallback::Panic && unmatched == Fallback::Panic {
+                    quote! { faux::unmatched(e) }
                 } else {
-                    let faux_ident =
-                        syn::Ident::new(&format!("_faux_{}", name), proc_macro2::Span::call_site());
-
-                    let mut args =
-                        args.iter()
-                            .zip(method_data.arg_types.iter())
-                            .map(|(ident, ty)| {
-                                if has_impl_trait(ty.0) {
-                                    quote! {
-                                        std::boxed::Box::new(#ident)
+                    // only methods that return a `Result` whose error
+                    // can be created from the error get it, other
+                    // methods panic
+                    let return_unstubbed = unstubbed == Fallback::Err;
+                    let return_unmatched = unmatched == Fallback::Err;
+                    quote! {{
+                        #[allow(unused_imports)]
+                        use faux::{
+                            PanicUnmatched as _, ReturnError as _, ReturnInvocationError as _,
+                            ReturnUnstubbed as _,
+                        };
+                        (&&&&faux::Unmatched::new(
+                            e,
+                            <Self>::#faux_ident #turbofish,
+                            #return_unstubbed,
+                            #return_unmatched,
+                        )).unmatched_output()
+                    }}
+                };
+
+                if self.returned_future.is_some() {
+                    let call = method_data.receiver.method_body(
+                        real_self,
+                        quote! { std::result::Result::Ok(#proxy_real) },
+                        quote! {
+                            std::result::Result::Err(_maybe_faux_faux.call_async(<Self>::#faux_ident #turbofish, #fn_name, #args, #generics_str))
+                        },
+                    )?;
+
+                    if real_boxed {
+                        syn::parse_quote! {{
+                            match #call {
+                                std::result::Result::Ok(_maybe_faux_future) => _maybe_faux_future,
+                                std::result::Result::Err(_maybe_faux_stub) => std::boxed::Box::pin(async move {
+                                    match _maybe_faux_stub {
+                                        std::result::Result::Ok(o) => o.await,
+                                        std::result::Result::Err(e) => #unmatched,
                                     }
-                                } else {
-                                    quote! { #ident }
+                                }),
+                            }
+                        }}
+                    } else {
+                        syn::parse_quote! {{
+                            let _maybe_faux_future = #call;
+                            #into_future(async move {
+                                match _maybe_faux_future {
+                                    std::result::Result::Ok(_maybe_faux_future) => #real_output,
+                                    std::result::Result::Err(std::result::Result::Ok(o)) => o.await,
+                                    std::result::Result::Err(std::result::Result::Err(e)) => #unmatched,
                                 }
-                            });
-
-                    let args = if args.len() == 1 {
-                        let arg = args.next().unwrap();
-                        quote! { #arg }
+                            })
+                        }}
+                    }
+                } else {
+                    let call_stub = if self.is_async {
+                        // async stubs are awaited within the mocked method
+                        quote! {
+                            match _maybe_faux_faux.call_async(<Self>::#faux_ident #turbofish, #fn_name, #args, #generics_str) {
+                                std::result::Result::Ok(o) => o.await,
+                                std::result::Result::Err(e) => #unmatched,
+                            }
+                        }
                     } else {
-                        quote! { (#(#args,)*) }
-                    };
-
-                    let fn_name = name.to_string();
-                    let mut generics_str = generic_idents
-                        .into_iter()
-                        .map(|i| i.to_string())
-                        .collect::<Vec<_>>()
-                        .join(",");
-                    generics_str.retain(|c| !c.is_whitespace());
-
-                    quote! {
-                        unsafe {
-                            match _maybe_faux_faux.call_stub(<Self>::#faux_ident #turbofish, #fn_name, #args, #generics_str) {
+                        quote! {
+                            match _maybe_faux_faux.call(<Self>::#faux_ident #turbofish, #fn_name, #args, #generics_str) {
                                 std::result::Result::Ok(o) => o,
-                                std::result::Result::Err(e) => panic!("{}", e),
+                                std::result::Result::Err(e) => #unmatched,
                             }
                         }
-                    }
-                };
+                    };
 
-                method_data
-                    .receiver
-                    .method_body(real_self, proxy_real, call_stub)?
+                    let ret =
+                        method_data
+                            .receiver
+                            .method_body(real_self, real_output, call_stub)?;
+                    syn::parse_quote! {{ #ret }}
+                }
             }
         };
 
-        Ok(syn::Block {
-            stmts: vec![syn::Stmt::Expr(ret, None)],
-            brace_token: Default::default(),
-        })
+        Ok(block)
     }
 
     pub fn create_when(&self) -> Option<Vec<syn::ImplItemFn>> {
         self.method_data
             .as_ref()
-            .filter(|m| !m.is_private)
-            .map(|m| m.create_when(self.output, self.name))
+            .map(|m| m.create_when(self.output, self.name, self.namespace()))
+    }
+
+    pub fn name(&self) -> &syn::Ident {
+        self.name
+    }
+
+    fn namespace(&self) -> Option<&syn::Ident> {
+        self.namespace.as_ref()
     }
 
     fn wrap_self(
@@ -378,12 +508,13 @@ impl MethodData<'_> {
         &self,
         output: Option<&syn::Type>,
         name: &syn::Ident,
+        namespace: Option<&syn::Ident>,
     ) -> Vec<syn::ImplItemFn> {
         let MethodData {
             arg_types,
             receiver,
             generics,
-            ..
+            vis,
         } = self;
         let mut receiver_ty = receiver.ty.clone();
         add_lifetime(&mut receiver_ty, &syn::parse(quote! { 'm }.into()).unwrap());
@@ -398,10 +529,10 @@ impl MethodData<'_> {
             })
             .collect::<Vec<_>>();
 
-        let when_ident =
-            syn::Ident::new(&format!("_when_{}", name), proc_macro2::Span::call_site());
-        let faux_ident =
-            syn::Ident::new(&format!("_faux_{}", name), proc_macro2::Span::call_site());
+        let when_ident = helper_ident("_when", namespace, name);
+        let wait_ident = helper_ident("_wait", namespace, name);
+        let calls_ident = helper_ident("_calls", namespace, name);
+        let faux_ident = helper_ident("_faux", namespace, name);
 
         let empty = syn::parse_quote! { () };
         let mut output = output.unwrap_or(&empty).clone();
@@ -418,7 +549,8 @@ impl MethodData<'_> {
         let turbofish = turbofish(&generic_idents);
 
         let when_method = syn::parse_quote! {
-            pub fn #when_ident<#generics_contents>(&'m mut self) -> faux::When<'m, #receiver_ty, (#(#arg_types),*), #output, faux::matcher::AnyInvocation> #generics_where_clause {
+            #[allow(non_snake_case)]
+            #vis fn #when_ident<#generics_contents>(&'m mut self) -> faux::When<'m, #receiver_ty, (#(#arg_types),*), #output, faux::matcher::AnyInvocation> #generics_where_clause {
                 match &mut self.0 {
                     faux::MaybeFaux::Faux(_maybe_faux_faux) => faux::When::new(
                         <Self>::#faux_ident #turbofish,
@@ -430,6 +562,37 @@ impl MethodData<'_> {
             }
         };
 
+        // the types of `&mut` arguments borrow `'m` but no mutable
+        // reference is ever handed out
+        let wait_method = syn::parse_quote! {
+            #[allow(clippy::mut_from_ref)]
+            #[allow(non_snake_case)]
+            #vis fn #wait_ident<#generics_contents>(&'m self) -> faux::WaitFor<'m, #receiver_ty, (#(#arg_types),*), #output, faux::matcher::AnyInvocation> #generics_where_clause {
+                match &self.0 {
+                    faux::MaybeFaux::Faux(_maybe_faux_faux) => faux::WaitFor::new(
+                        <Self>::#faux_ident #turbofish,
+                        #name_str,
+                        _maybe_faux_faux
+                    ),
+                    faux::MaybeFaux::Real(_) => panic!("not allowed to wait for calls on a real instance!"),
+                }
+            }
+        };
+
+        let calls_method = syn::parse_quote! {
+            #[allow(non_snake_case)]
+            #vis fn #calls_ident<#generic_params>(&self) -> faux::Calls #generics_where_clause {
+                match &self.0 {
+                    faux::MaybeFaux::Faux(_maybe_faux_faux) => faux::Calls::new(
+                        <Self>::#faux_ident #turbofish,
+                        #name_str,
+                        _maybe_faux_faux
+                    ),
+                    faux::MaybeFaux::Real(_) => panic!("not allowed to get the calls of a real instance!"),
+                }
+            }
+        };
+
         let proxy = quote! { <Self>::#faux_ident #turbofish };
 
         let panic_message = format!("do not call this ({proxy})");
@@ -437,15 +600,109 @@ impl MethodData<'_> {
         let faux_method = syn::parse_quote! {
             #[allow(clippy::needless_arbitrary_self_type)]
             #[allow(clippy::boxed_local)]
-            pub fn #faux_ident <#generics_contents> (self: #receiver_ty, _: (#(#arg_types),*)) -> #output #generics_where_clause {
+            #[allow(non_snake_case)]
+            #vis fn #faux_ident <#generics_contents> (self: #receiver_ty, _: (#(#arg_types),*)) -> #output #generics_where_clause {
                 panic!(concat!(#panic_message, "{:?}"), #proxy as *const ())
             }
         };
 
-        vec![when_method, faux_method]
+        vec![when_method, wait_method, calls_method, faux_method]
+    }
+}
+
+/// The namespace of the helpers of a trait's methods, e.g., `Read`
+/// for `impl Read`
+///
+/// The generic arguments of the trait are hashed into the namespace,
+/// e.g., `From_{hash}` for `impl From<A>`, so its helpers do not clash
+/// with those of `impl From<B>`. Lifetimes are ignored, as are the
+/// arguments of an impl that is generic over them, e.g., `impl<T>
+/// From<T>`, as `when!` cannot name them.
+///
+/// Only the last segment of each path is used so the trait and its
+/// arguments may be spelled with or without their module, e.g.,
+/// `io::Read` or `From<path::Ty>`.
+pub fn trait_namespace(trait_: &syn::Path, impl_generics: &syn::Generics) -> syn::Ident {
+    let mut segment = trait_.segments.last().unwrap().clone();
+    LastSegments.visit_path_segment_mut(&mut segment);
+    let args: Vec<_> = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().collect(),
+        _ => vec![],
+    };
+
+    let params: Vec<_> = impl_generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Type(ty) => Some(&ty.ident),
+            syn::GenericParam::Const(c) => Some(&c.ident),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let generic = args
+        .iter()
+        .any(|arg| mentions(arg.to_token_stream(), &params));
+
+    let ident = &segment.ident;
+    if args.is_empty() || generic {
+        return ident.clone();
+    }
+
+    // the same type may be written with different spacing
+    let mut args = quote! { #(#args),* }.to_string();
+    args.retain(|c| !c.is_whitespace());
+    let mut hasher = DefaultHasher::new();
+    args.hash(&mut hasher);
+    quote::format_ident!("{}_{:016x}", ident, hasher.finish())
+}
+
+// reduces every path to its last segment and removes lifetimes, so
+// types are written the same way wherever they are named
+struct LastSegments;
+
+impl VisitMut for LastSegments {
+    fn visit_path_mut(&mut self, path: &mut syn::Path) {
+        visit_mut::visit_path_mut(self, path);
+        let last = path.segments.pop().unwrap().into_value();
+        *path = last.into();
+    }
+
+    fn visit_angle_bracketed_generic_arguments_mut(
+        &mut self,
+        args: &mut syn::AngleBracketedGenericArguments,
+    ) {
+        args.args = std::mem::take(&mut args.args)
+            .into_iter()
+            .filter(|arg| !matches!(arg, GenericArgument::Lifetime(_)))
+            .collect();
+        visit_mut::visit_angle_bracketed_generic_arguments_mut(self, args);
+    }
+
+    fn visit_type_reference_mut(&mut self, reference: &mut syn::TypeReference) {
+        reference.lifetime = None;
+        visit_mut::visit_type_reference_mut(self, reference);
     }
 }
 
+// whether the tokens contain any of the identifiers
+fn mentions(tokens: TokenStream, idents: &[&syn::Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => idents.contains(&&ident),
+        proc_macro2::TokenTree::Group(group) => mentions(group.stream(), idents),
+        _ => false,
+    })
+}
+
+/// The identifier of a generated helper, e.g., `_when_{name}` or
+/// `_when_{Trait}_{name}` for trait methods
+pub fn helper_ident(prefix: &str, namespace: Option<&syn::Ident>, name: &syn::Ident) -> syn::Ident {
+    let ident = match namespace {
+        None => format!("{prefix}_{name}"),
+        Some(namespace) => format!("{prefix}_{namespace}_{name}"),
+    };
+    syn::Ident::new(&ident, proc_macro2::Span::call_site())
+}
+
 // traverse a type, and add the provided lifetime to references that don't have any lifetime yet.
 // Eg(pseudocode): `add_lifetime(Result<Option<&str>, ()>, 'a)`
 // will return
@@ -476,6 +733,74 @@ fn add_lifetime(t: &mut Type, new_lifetime: &Lifetime) {
     }
 }
 
+// the kind of future and its `T` for `impl Future<Output = T>`,
+// `Pin<Box<dyn Future<Output = T>>>` and `BoxFuture<'_, T>`
+fn returned_future(ty: &Type) -> Option<(ReturnedFuture, &Type)> {
+    match ty {
+        Type::ImplTrait(impl_trait) => {
+            future_output(&impl_trait.bounds).map(|output| (ReturnedFuture::Impl, output))
+        }
+        Type::Path(path) => {
+            let segment = path.path.segments.last()?;
+            let output = match segment.ident.to_string().as_str() {
+                "BoxFuture" | "LocalBoxFuture" => type_args(segment).next()?,
+                "Pin" => match type_args(segment).next()? {
+                    Type::Path(boxed) => {
+                        let boxed = boxed.path.segments.last()?;
+                        if boxed.ident != "Box" {
+                            return None;
+                        }
+                        match type_args(boxed).next()? {
+                            Type::TraitObject(object) => future_output(&object.bounds)?,
+                            _ => return None,
+                        }
+                    }
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            Some((ReturnedFuture::Boxed, output))
+        }
+        Type::Group(g) => returned_future(&g.elem),
+        Type::Paren(p) => returned_future(&p.elem),
+        _ => None,
+    }
+}
+
+// the `T` of a `Future<Output = T>` bound
+fn future_output<'t>(
+    bounds: impl IntoIterator<Item = &'t syn::TypeParamBound>,
+) -> Option<&'t Type> {
+    bounds.into_iter().find_map(|bound| {
+        let syn::TypeParamBound::Trait(bound) = bound else {
+            return None;
+        };
+        let segment = bound.path.segments.last()?;
+        if segment.ident != "Future" {
+            return None;
+        }
+        let PathArguments::AngleBracketed(args) = &segment.arguments else {
+            return None;
+        };
+        args.args.iter().find_map(|arg| match arg {
+            GenericArgument::AssocType(assoc) if assoc.ident == "Output" => Some(&assoc.ty),
+            _ => None,
+        })
+    })
+}
+
+// the generic type arguments of a path segment, e.g., `T` in `Box<T>`
+fn type_args(segment: &PathSegment) -> impl Iterator<Item = &Type> {
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => Some(&args.args),
+        _ => None,
+    };
+    args.into_iter().flatten().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
 fn unhandled_self_return(spanned: impl Spanned) -> darling::Error {
     darling::Error::custom("faux: the return type refers to the mocked struct in a way that faux cannot handle. Split this function into an `impl` block not marked by #[faux::methods]. If you believe this is a mistake or it's a case that should be handled by faux please file an issue").with_span(&spanned)
 }
@@ -0,0 +1,217 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use crate::Faux;
+
+/// The calls made to a stubbed method.
+///
+/// Created by [`calls!`](crate::calls!). It is a snapshot of how many
+/// calls were made up to that point. Only calls that were answered by
+/// a stub are included.
+///
+/// The calls are counted by their status, which tells whether the
+/// future returned by an async method was polled to completion,
+/// dropped before completing, or never polled, which comes in handy
+/// when testing for cancellation safety. Calls to sync methods
+/// complete unless their stub panics.
+///
+/// Methods that return a future, e.g., `impl Future<Output = T>`,
+/// reach their stub as soon as they are called. The body of an
+/// `async fn`, however, only runs once its future is first polled, so
+/// a call to an `async fn` that is never polled is not included.
+///
+/// # Examples
+///
+/// ```
+/// #[faux::create]
+/// pub struct Service {}
+///
+/// #[faux::methods]
+/// impl Service {
+///     pub async fn fetch(&self) -> u32 {
+///         /* implementation code */
+///         # panic!()
+///     }
+/// }
+///
+/// # fn main() {
+/// use futures::FutureExt;
+///
+/// let mut service = Service::faux();
+/// faux::when!(service.fetch).then_async(|_| futures::future::pending());
+///
+/// // poll the call once and then drop it
+/// assert!(service.fetch().now_or_never().is_none());
+///
+/// let calls = faux::calls!(service.fetch);
+/// assert_eq!(calls.len(), 1);
+/// assert_eq!(calls.cancelled(), 1);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Calls {
+    not_polled: usize,
+    pending: usize,
+    completed: usize,
+    cancelled: usize,
+    panicked: usize,
+}
+
+impl Calls {
+    #[doc(hidden)]
+    /// Do *NOT* call this function directly.
+    /// This should only be called by the generated code from #[faux::methods]
+    pub fn new<R, I, O>(id: fn(R, I) -> O, fn_name: &'static str, faux: &Faux) -> Self {
+        // methods that were never stubbed were never called
+        // Safety: the generated code always pairs `id` with the same
+        // name, see the docs on `mock::Store`
+        match unsafe { faux.store.get(id, fn_name, "") } {
+            Ok(mock) => mock.calls(),
+            Err(_) => Calls::default(),
+        }
+    }
+
+    /// How many calls were made.
+    pub fn len(&self) -> usize {
+        self.not_polled + self.pending + self.completed + self.cancelled + self.panicked
+    }
+
+    /// Whether no calls were made.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many calls completed.
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    /// How many calls were dropped after being polled but before
+    /// completing.
+    pub fn cancelled(&self) -> usize {
+        self.cancelled
+    }
+
+    /// How many calls panicked, either in the stub when called or in
+    /// the stubbed future when polled.
+    ///
+    /// A polled call that is dropped while its thread is panicking is
+    /// counted as panicked rather than cancelled.
+    pub fn panicked(&self) -> usize {
+        self.panicked
+    }
+
+    /// How many calls have been polled but have not completed yet.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// How many calls have never been polled, whether or not they were
+    /// dropped.
+    ///
+    /// Always zero for an `async fn` as its stub is only reached once
+    /// its future is polled.
+    pub fn not_polled(&self) -> usize {
+        self.not_polled
+    }
+}
+
+/// Counts the calls to an async method as they change status, and
+/// the calls to any method whose stub panicked
+///
+/// Each count only goes up so they are read without locking: a call
+/// is counted as polled before it is counted as completed or
+/// cancelled, so reading the counts in the opposite order never
+/// counts a call twice.
+#[derive(Debug, Default)]
+pub(crate) struct Tracker {
+    called: AtomicUsize,
+    polled: AtomicUsize,
+    completed: AtomicUsize,
+    cancelled: AtomicUsize,
+    // calls whose future panicked once polled
+    panicked_polling: AtomicUsize,
+    // calls whose stub panicked when called, which were never tracked
+    panicked: AtomicUsize,
+}
+
+impl Tracker {
+    /// Starts tracking a call
+    pub(crate) fn call(self: &Arc<Self>) -> Call {
+        self.called.fetch_add(1, Ordering::SeqCst);
+        Call(self.clone())
+    }
+
+    /// Counts the call as panicked if the stub answering it panics
+    /// before the returned guard is dropped
+    pub(crate) fn answer(&self) -> Answering<'_> {
+        Answering(self)
+    }
+
+    /// The calls so far, along with the completed calls to a sync
+    /// method
+    pub(crate) fn calls(&self, completed_sync: usize) -> Calls {
+        let panicked_polling = self.panicked_polling.load(Ordering::SeqCst);
+        let cancelled = self.cancelled.load(Ordering::SeqCst);
+        let completed = self.completed.load(Ordering::SeqCst);
+        let polled = self.polled.load(Ordering::SeqCst);
+        let called = self.called.load(Ordering::SeqCst);
+        Calls {
+            not_polled: called - polled,
+            pending: polled - completed - cancelled - panicked_polling,
+            completed: completed + completed_sync,
+            cancelled,
+            panicked: panicked_polling + self.panicked.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Counts its call as panicked if dropped while panicking
+pub(crate) struct Answering<'t>(&'t Tracker);
+
+impl Drop for Answering<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.panicked.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// A call to an async method that has not been polled yet
+pub(crate) struct Call(Arc<Tracker>);
+
+impl Call {
+    /// Marks the call as polled until the returned guard is either
+    /// completed or dropped
+    pub(crate) fn poll(self) -> Polling {
+        self.0.polled.fetch_add(1, Ordering::SeqCst);
+        Polling(Some(self.0))
+    }
+}
+
+/// Marks its call as cancelled, or as panicked if its thread is
+/// panicking, if dropped before completing
+pub(crate) struct Polling(Option<Arc<Tracker>>);
+
+impl Polling {
+    pub(crate) fn complete(mut self) {
+        if let Some(tracker) = self.0.take() {
+            tracker.completed.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Drop for Polling {
+    fn drop(&mut self) {
+        if let Some(tracker) = self.0.take() {
+            let status = if std::thread::panicking() {
+                &tracker.panicked_polling
+            } else {
+                &tracker.cancelled
+            };
+            status.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
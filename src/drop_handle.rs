@@ -0,0 +1,116 @@
+use std::{
+    fmt::{self, Formatter},
+    sync::Weak,
+};
+
+use crate::MaybeFaux;
+
+/// Tracks whether a mock instance was dropped.
+///
+/// Created by [`drop_handle!`](crate::drop_handle). A mock instance
+/// and all of its clones share their stubs, so the mock is only
+/// considered dropped once every clone has been dropped.
+///
+/// The real instance's `Drop` implementation, if any, is never called
+/// for mocks. Use this handle to verify that the code under test
+/// released a mocked resource instead.
+///
+/// # Examples
+///
+/// ```
+/// #[faux::create]
+/// pub struct Connection {
+///     /* snip */
+///     # _inner: u8,
+/// }
+///
+/// #[faux::methods]
+/// impl Connection {
+///     pub fn send(&self, message: &str) {
+///         /* snip */
+///         # panic!()
+///     }
+/// }
+///
+/// fn send_and_close(connection: Connection) {
+///     connection.send("bye");
+/// }
+///
+/// # fn main() {
+/// let mut connection = Connection::faux();
+/// faux::when!(connection.send).then(|_| {});
+///
+/// let handle = faux::drop_handle!(connection);
+/// assert!(!handle.is_dropped());
+///
+/// send_and_close(connection);
+/// faux::verify_dropped(&handle);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DropHandle {
+    struct_name: &'static str,
+    instances: Weak<()>,
+}
+
+impl DropHandle {
+    #[doc(hidden)]
+    /// Do *NOT* call this function directly.
+    /// This should only be called by the generated code from `faux::drop_handle!`
+    pub fn new<T>(maybe_faux: &MaybeFaux<T>) -> Self {
+        match maybe_faux {
+            MaybeFaux::Faux(faux) => DropHandle {
+                struct_name: faux.store.struct_name,
+                instances: std::sync::Arc::downgrade(&faux.instances),
+            },
+            MaybeFaux::Real(_) => panic!("faux::drop_handle! can only be used on mock instances"),
+        }
+    }
+
+    /// Returns whether the mock instance and all of its clones have
+    /// been dropped.
+    pub fn is_dropped(&self) -> bool {
+        self.instances.strong_count() == 0
+    }
+}
+
+impl fmt::Debug for DropHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropHandle")
+            .field("struct_name", &self.struct_name)
+            .field("dropped", &self.is_dropped())
+            .finish()
+    }
+}
+
+/// Panics unless the mock instance tracked by `handle` was dropped.
+///
+/// See [`DropHandle`] for an example.
+///
+/// # Panics
+///
+/// Panics if the mock instance or any of its clones is still alive.
+#[track_caller]
+pub fn verify_dropped(handle: &DropHandle) {
+    let alive = handle.instances.strong_count();
+    if alive > 0 {
+        panic!(
+            "faux: expected mock of '{}' to be dropped but {} instance(s) are still alive",
+            handle.struct_name, alive
+        );
+    }
+}
+
+/// Returns a [`DropHandle`] to verify that a mock instance is dropped.
+///
+/// See [`DropHandle`] for an example.
+///
+/// # Panics
+///
+/// Panics if the instance is not a mock.
+#[macro_export]
+macro_rules! drop_handle {
+    ($mock:expr) => {
+        $crate::DropHandle::new(&$mock.0)
+    };
+}
@@ -0,0 +1,223 @@
+use std::{
+    fmt::{self, Formatter},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+/// Pauses stubbed calls until the test releases them.
+///
+/// Stub a method using [`When::then_gated`] to have its calls wait
+/// at the gate. Sync methods park the calling thread while async
+/// methods return a pending future. Use [`wait_entered`] to wait
+/// until a call reaches the gate and [`release`] to let a call
+/// through. Together they let a test choreograph the exact order of
+/// concurrent calls, e.g., to test timeouts or cancellation.
+///
+/// Cloning a gate returns a handle to the same gate.
+///
+/// Calls stay parked if the gate is never released so make sure to
+/// release every call that enters.
+///
+/// # Examples
+///
+/// ```
+/// #[faux::create]
+/// pub struct Service {}
+///
+/// #[faux::methods]
+/// impl Service {
+///     pub fn fetch(&self) -> u32 {
+///         /* implementation code */
+///         # panic!()
+///     }
+/// }
+///
+/// # fn main() {
+/// use std::sync::Arc;
+///
+/// let gate = faux::Gate::new();
+/// let mut mock = Service::faux();
+/// faux::when!(mock.fetch).then_gated(&gate, 3);
+///
+/// let mock = Arc::new(mock);
+/// let worker = {
+///     let mock = mock.clone();
+///     std::thread::spawn(move || mock.fetch())
+/// };
+///
+/// // the worker is now parked inside `fetch`
+/// gate.wait_entered();
+/// assert!(!worker.is_finished());
+///
+/// gate.release();
+/// assert_eq!(worker.join().unwrap(), 3);
+/// # }
+/// ```
+///
+/// [`When::then_gated`]: crate::When::then_gated
+/// [`wait_entered`]: Gate::wait_entered
+/// [`release`]: Gate::release
+#[derive(Clone, Default)]
+pub struct Gate {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    // calls that reached the gate
+    entered: usize,
+    // entered calls already reported by `wait_entered`
+    observed: usize,
+    // calls allowed through that have yet to pass
+    permits: usize,
+    // async calls and waits to wake up on a change
+    wakers: Vec<Waker>,
+}
+
+impl Gate {
+    /// Creates a closed gate.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets one call through the gate.
+    ///
+    /// If no call is waiting at the gate, the next call to reach it
+    /// passes through without waiting.
+    pub fn release(&self) {
+        self.update(|state| state.permits += 1);
+    }
+
+    /// Blocks until a call reaches the gate.
+    ///
+    /// Each call is reported only once, so calling this `n` times
+    /// waits for `n` calls to reach the gate.
+    pub fn wait_entered(&self) {
+        let state = self.lock();
+        let mut state = self
+            .inner
+            .changed
+            .wait_while(state, |state| state.entered == state.observed)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.observed += 1;
+    }
+
+    /// Analog of [`wait_entered`](Gate::wait_entered) that gives up
+    /// after `timeout`.
+    ///
+    /// Returns whether a call reached the gate in time.
+    pub fn wait_entered_timeout(&self, timeout: Duration) -> bool {
+        let state = self.lock();
+        let (mut state, result) = self
+            .inner
+            .changed
+            .wait_timeout_while(state, timeout, |state| state.entered == state.observed)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if result.timed_out() {
+            return false;
+        }
+        state.observed += 1;
+        true
+    }
+
+    /// Async analog of [`wait_entered`](Gate::wait_entered).
+    pub fn entered(&self) -> impl Future<Output = ()> + Send + 'static {
+        Wait {
+            gate: self.clone(),
+            ready: |state: &mut State| {
+                let ready = state.entered > state.observed;
+                if ready {
+                    state.observed += 1;
+                }
+                ready
+            },
+        }
+    }
+
+    /// Records a call reaching the gate
+    pub(crate) fn enter(&self) {
+        self.update(|state| state.entered += 1);
+    }
+
+    /// Parks the current thread until the call is released
+    pub(crate) fn pass_blocking(&self) {
+        let state = self.lock();
+        let mut state = self
+            .inner
+            .changed
+            .wait_while(state, |state| state.permits == 0)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.permits -= 1;
+    }
+
+    /// Resolves once the call is released
+    pub(crate) fn pass(&self) -> impl Future<Output = ()> + Send + 'static {
+        Wait {
+            gate: self.clone(),
+            ready: |state: &mut State| {
+                let ready = state.permits > 0;
+                if ready {
+                    state.permits -= 1;
+                }
+                ready
+            },
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut State)) {
+        let wakers = {
+            let mut state = self.lock();
+            f(&mut state);
+            std::mem::take(&mut state.wakers)
+        };
+        self.inner.changed.notify_all();
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // a panicking test should not poison the gate for other threads
+        self.inner
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl fmt::Debug for Gate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("Gate")
+            .field("entered", &state.entered)
+            .field("permits", &state.permits)
+            .finish()
+    }
+}
+
+/// Resolves once `ready` returns true for the gate's state
+struct Wait {
+    gate: Gate,
+    ready: fn(&mut State) -> bool,
+}
+
+impl Future for Wait {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.gate.lock();
+        if (self.ready)(&mut state) {
+            return Poll::Ready(());
+        }
+
+        state.wakers.push(cx.waker().clone());
+        Poll::Pending
+    }
+}
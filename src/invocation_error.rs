@@ -0,0 +1,549 @@
+use std::{
+    fmt::{self, Formatter},
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+
+use crate::{
+    mock::{self, stub},
+    MaybeFaux,
+};
+
+pub use stub::Error as StubError;
+
+/// The error produced when a mock instance cannot handle a call to
+/// one of its methods.
+///
+/// This happens when the method was never stubbed, or when none of
+/// its stubs could handle the invocation (e.g., the arguments did not
+/// match, or the stub was exhausted).
+///
+/// By default, the mocked method panics with this error as its
+/// message. Use [`on_unmatched!`](crate::on_unmatched) to also
+/// receive the error, e.g., to report it at the end of a test rather
+/// than relying on a panic that happened deep inside another thread
+/// or an async task. Methods that return a `Result` may return the
+/// error instead of panicking; see
+/// [`unmatched`](crate::methods#unmatched).
+///
+/// # Examples
+///
+/// ```
+/// #[faux::create]
+/// pub struct Foo {}
+///
+/// #[faux::methods]
+/// impl Foo {
+///     pub fn get(&self, a: u32) -> u32 {
+///         /* implementation code */
+///         # panic!()
+///     }
+/// }
+///
+/// # fn main() {
+/// use std::sync::{Arc, Mutex};
+///
+/// let mut mock = Foo::faux();
+/// faux::when!(mock.get(3)).then_return(5);
+///
+/// let errors = Arc::new(Mutex::new(vec![]));
+/// let handler = {
+///     let errors = errors.clone();
+///     faux::on_unmatched!(mock, move |e| errors.lock().unwrap().push(e.clone()))
+/// };
+///
+/// // the method still panics as it has no value to return
+/// let result = std::panic::catch_unwind(move || mock.get(4));
+/// assert!(result.is_err());
+/// drop(handler);
+///
+/// let errors = errors.lock().unwrap();
+/// let error = &errors[0];
+/// assert_eq!(error.struct_name(), "Foo");
+/// assert_eq!(error.method(), "get");
+/// assert!(!error.never_stubbed());
+///
+/// let (index, stub_error) = error.closest().unwrap();
+/// assert_eq!(index, 0);
+/// assert!(matches!(stub_error, faux::StubError::NotMatched(_)));
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct InvocationError {
+    struct_name: &'static str,
+    fn_name: &'static str,
+    generics: &'static str,
+    stub_error: mock::InvocationError,
+}
+
+impl InvocationError {
+    pub(crate) fn new(
+        struct_name: &'static str,
+        fn_name: &'static str,
+        generics: &'static str,
+        stub_error: mock::InvocationError,
+    ) -> Self {
+        InvocationError {
+            struct_name,
+            fn_name,
+            generics,
+            stub_error,
+        }
+    }
+
+    /// The name of the mocked struct.
+    pub fn struct_name(&self) -> &'static str {
+        self.struct_name
+    }
+
+    /// The name of the method that was called.
+    pub fn method(&self) -> &'static str {
+        self.fn_name
+    }
+
+    /// The generic types of the method that was called, separated by
+    /// commas.
+    ///
+    /// Empty if the method is not generic.
+    pub fn generics(&self) -> &'static str {
+        self.generics
+    }
+
+    /// Returns `true` if the method was called but never stubbed.
+    pub fn never_stubbed(&self) -> bool {
+        matches!(self.stub_error, mock::InvocationError::NeverStubbed)
+    }
+
+    /// Returns an [`Unstubbed`] error if the method was called but
+    /// never stubbed.
+    pub fn unstubbed(&self) -> Option<Unstubbed> {
+        if self.never_stubbed() {
+            Some(Unstubbed {
+                struct_name: self.struct_name,
+                fn_name: self.fn_name,
+                generics: self.generics,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the error of every stub for the method in the order
+    /// they were attempted.
+    ///
+    /// Each error is paired with the stub's number. Stubs are
+    /// numbered in the order they were added, starting at zero.
+    ///
+    /// Empty if the method was never stubbed.
+    pub fn stub_errors(&self) -> impl Iterator<Item = (usize, &StubError)> {
+        let errors = match &self.stub_error {
+            mock::InvocationError::NeverStubbed => &[][..],
+            mock::InvocationError::Stub(errors) => &errors[..],
+        };
+        errors.iter().map(|(index, error)| (*index, error))
+    }
+
+    /// Returns the stub errors with the closest stubs first.
+    ///
+    /// The closest stub is the one with the fewest arguments that
+    /// failed to match. Ties keep the order the stubs were attempted.
+    pub fn ranked_stub_errors(&self) -> Vec<(usize, &StubError)> {
+        let mut errors: Vec<_> = self.stub_errors().collect();
+        errors.sort_by_key(|(_, e)| e.mismatched_args().unwrap_or(usize::MAX));
+        errors
+    }
+
+    /// Returns the closest stub's number and error.
+    ///
+    /// `None` if the method was never stubbed.
+    pub fn closest(&self) -> Option<(usize, &StubError)> {
+        self.ranked_stub_errors().into_iter().next()
+    }
+}
+
+impl std::error::Error for InvocationError {}
+
+impl fmt::Display for InvocationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let generics = if self.generics.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", self.generics)
+        };
+
+        let mut errors = self.ranked_stub_errors().into_iter();
+        let (index, closest) = match errors.next() {
+            None => {
+                return write!(
+                    f,
+                    "`{}::{}{}` was called but never stubbed",
+                    self.struct_name, self.fn_name, generics
+                )
+            }
+            Some(closest) => closest,
+        };
+
+        write!(
+            f,
+            "`{}::{}{}` had no suitable stubs. The closest stub (#{}) failed because:\n✗ {}",
+            self.struct_name, self.fn_name, generics, index, closest
+        )?;
+
+        if errors.len() > 0 {
+            f.write_str("\n\nOther stubs failed because:")?;
+        }
+        errors.try_for_each(|(index, e)| write!(f, "\n  ✗ #{}: {}", index, e.summary()))
+    }
+}
+
+/// The error returned by methods that were called but never stubbed.
+///
+/// Only returned when the `impl` block is tagged with
+/// `#[faux::methods(unstubbed = "err")]` and the method returns a
+/// `Result` whose error type implements `From<Unstubbed>`. Methods
+/// that return [`std::io::Result`] work out of the box.
+///
+/// This lets tests exercise how the code under test handles a
+/// collaborator that fails, without stubbing every method to fail.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Debug)]
+/// pub enum Error {
+///     Unstubbed(faux::Unstubbed),
+/// }
+///
+/// impl From<faux::Unstubbed> for Error {
+///     fn from(unstubbed: faux::Unstubbed) -> Self {
+///         Error::Unstubbed(unstubbed)
+///     }
+/// }
+///
+/// #[faux::create]
+/// pub struct Client {}
+///
+/// #[faux::methods(unstubbed = "err")]
+/// impl Client {
+///     pub fn fetch(&self, id: u32) -> Result<String, Error> {
+///         /* implementation code */
+///         # panic!()
+///     }
+/// }
+///
+/// # fn main() {
+/// let client = Client::faux();
+///
+/// let Error::Unstubbed(unstubbed) = client.fetch(3).unwrap_err();
+/// assert_eq!(unstubbed.struct_name(), "Client");
+/// assert_eq!(unstubbed.method(), "fetch");
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unstubbed {
+    struct_name: &'static str,
+    fn_name: &'static str,
+    generics: &'static str,
+}
+
+impl Unstubbed {
+    /// The name of the mocked struct.
+    pub fn struct_name(&self) -> &'static str {
+        self.struct_name
+    }
+
+    /// The name of the method that was called.
+    pub fn method(&self) -> &'static str {
+        self.fn_name
+    }
+
+    /// The generic types of the method that was called, separated by
+    /// commas.
+    ///
+    /// Empty if the method is not generic.
+    pub fn generics(&self) -> &'static str {
+        self.generics
+    }
+}
+
+impl std::error::Error for Unstubbed {}
+
+impl fmt::Display for Unstubbed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}::{}", self.struct_name, self.fn_name)?;
+        if !self.generics.is_empty() {
+            write!(f, "<{}>", self.generics)?;
+        }
+        f.write_str("` was called but never stubbed")
+    }
+}
+
+impl From<Unstubbed> for std::io::Error {
+    fn from(unstubbed: Unstubbed) -> Self {
+        std::io::Error::new(std::io::ErrorKind::Other, unstubbed)
+    }
+}
+
+type Handler = Arc<dyn Fn(&InvocationError) + Send + Sync>;
+
+/// The handlers registered on a mock instance and its clones.
+#[derive(Default)]
+pub(crate) struct Handlers {
+    handlers: Mutex<Vec<(usize, Handler)>>,
+    next: AtomicUsize,
+}
+
+impl Handlers {
+    /// Passes the error to every registered handler.
+    pub(crate) fn report(&self, error: &InvocationError) {
+        // clone the handlers so they run without the lock held
+        let handlers: Vec<_> = self
+            .lock()
+            .iter()
+            .map(|(_, handler)| handler.clone())
+            .collect();
+        handlers.iter().for_each(|handler| handler(error));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(usize, Handler)>> {
+        // a panicking handler should not stop other handlers from working
+        self.handlers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for Handlers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handlers")
+            .field("len", &self.lock().len())
+            .finish()
+    }
+}
+
+/// Keeps a handler registered through
+/// [`on_unmatched!`](crate::on_unmatched).
+///
+/// The handler is unregistered when this is dropped.
+#[derive(Debug)]
+#[must_use = "the handler is unregistered when dropped"]
+pub struct UnmatchedHandler {
+    id: usize,
+    handlers: Weak<Handlers>,
+}
+
+impl UnmatchedHandler {
+    #[doc(hidden)]
+    /// Do *NOT* call this function directly.
+    /// This should only be called by the generated code from `faux::on_unmatched!`
+    pub fn new<T>(
+        maybe_faux: &MaybeFaux<T>,
+        handler: impl Fn(&InvocationError) + Send + Sync + 'static,
+    ) -> Self {
+        let handlers = match maybe_faux {
+            MaybeFaux::Faux(faux) => &faux.handlers,
+            MaybeFaux::Real(_) => panic!("faux::on_unmatched! can only be used on mock instances"),
+        };
+        let id = handlers.next.fetch_add(1, Ordering::Relaxed);
+        handlers.lock().push((id, Arc::new(handler)));
+        UnmatchedHandler {
+            id,
+            handlers: Arc::downgrade(handlers),
+        }
+    }
+}
+
+impl Drop for UnmatchedHandler {
+    fn drop(&mut self) {
+        if let Some(handlers) = self.handlers.upgrade() {
+            handlers.lock().retain(|(id, _)| *id != self.id);
+        }
+    }
+}
+
+/// Registers a handler that receives every [`InvocationError`] of a
+/// mock instance and its clones.
+///
+/// The handler is called before the mocked method panics with the
+/// error, or returns it when the method may return errors (see
+/// [`unstubbed`](crate::methods#unstubbed) and
+/// [`unmatched`](crate::methods#unmatched)). The panic may be
+/// swallowed by a thread or an async runtime; the handler lets a test
+/// harness collect these errors and report them once the test is
+/// done.
+///
+/// The handler is unregistered once the returned [`UnmatchedHandler`]
+/// is dropped.
+///
+/// See [`InvocationError`] for an example.
+///
+/// # Panics
+///
+/// Panics if the instance is not a mock.
+#[macro_export]
+macro_rules! on_unmatched {
+    ($mock:expr, $handler:expr $(,)?) => {
+        $crate::UnmatchedHandler::new(&$mock.0, $handler)
+    };
+}
+
+/// Panics with an unmatched invocation.
+///
+/// The error was already reported to the handlers of the mock.
+///
+/// Exposed so generated code can use it but purposefully not
+/// documented.
+#[doc(hidden)]
+#[track_caller]
+pub fn unmatched(error: InvocationError) -> ! {
+    panic!("{}", error)
+}
+
+/// An invocation that a mock instance could not handle, turned into
+/// the output of the mocked method by `#[faux::methods(unstubbed =
+/// "err")]` or `#[faux::methods(unmatched = "err")]`.
+///
+/// Generated code calls `(&&&&Unmatched::new(..)).unmatched_output()`
+/// so that method resolution picks the first of these that applies:
+///
+/// * [`ReturnError`] for a `Result` whose error implements both
+///   `From<Unstubbed>` and `From<InvocationError>`.
+/// * [`ReturnInvocationError`] for a `Result` whose error implements
+///   `From<InvocationError>`.
+/// * [`ReturnUnstubbed`] for a `Result` whose error implements
+///   `From<Unstubbed>`.
+/// * [`PanicUnmatched`] for every other method.
+///
+/// Each of them panics with the error unless the `impl` block opted
+/// into returning it.
+///
+/// Exposed so generated code can use it but purposefully not
+/// documented.
+#[doc(hidden)]
+pub struct Unmatched<O> {
+    error: InvocationError,
+    return_unstubbed: bool,
+    return_unmatched: bool,
+    output: PhantomData<fn() -> O>,
+}
+
+impl<O> Unmatched<O> {
+    /// Takes the id of the mocked method only to infer its output.
+    ///
+    /// `return_unstubbed` and `return_unmatched` tell whether to
+    /// return the error when the method was never stubbed or when
+    /// none of its stubs matched, respectively.
+    pub fn new<R, I>(
+        error: InvocationError,
+        _id: fn(R, I) -> O,
+        return_unstubbed: bool,
+        return_unmatched: bool,
+    ) -> Self {
+        Unmatched {
+            error,
+            return_unstubbed,
+            return_unmatched,
+            output: PhantomData,
+        }
+    }
+
+    fn unstubbed(&self) -> Option<Unstubbed> {
+        if self.return_unstubbed {
+            self.error.unstubbed()
+        } else {
+            None
+        }
+    }
+
+    fn unmatched(&self) -> Option<InvocationError> {
+        if self.return_unmatched && !self.error.never_stubbed() {
+            Some(self.error.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns an `Err` created from an [`Unstubbed`] error if the method
+/// was never stubbed, or from the [`InvocationError`] otherwise.
+///
+/// Exposed so generated code can use it but purposefully not
+/// documented.
+#[doc(hidden)]
+pub trait ReturnError<O> {
+    fn unmatched_output(&self) -> O;
+}
+
+impl<T, E> ReturnError<Result<T, E>> for &&&Unmatched<Result<T, E>>
+where
+    E: From<Unstubbed> + From<InvocationError>,
+{
+    #[track_caller]
+    fn unmatched_output(&self) -> Result<T, E> {
+        if let Some(unstubbed) = self.unstubbed() {
+            return Err(E::from(unstubbed));
+        }
+        match self.unmatched() {
+            Some(error) => Err(E::from(error)),
+            None => unmatched(self.error.clone()),
+        }
+    }
+}
+
+/// Returns an `Err` created from the [`InvocationError`] if none of
+/// the method's stubs matched.
+///
+/// Exposed so generated code can use it but purposefully not
+/// documented.
+#[doc(hidden)]
+pub trait ReturnInvocationError<O> {
+    fn unmatched_output(&self) -> O;
+}
+
+impl<T, E: From<InvocationError>> ReturnInvocationError<Result<T, E>>
+    for &&Unmatched<Result<T, E>>
+{
+    #[track_caller]
+    fn unmatched_output(&self) -> Result<T, E> {
+        match self.unmatched() {
+            Some(error) => Err(E::from(error)),
+            None => unmatched(self.error.clone()),
+        }
+    }
+}
+
+/// Returns an `Err` created from an [`Unstubbed`] error if the method
+/// was never stubbed.
+///
+/// Exposed so generated code can use it but purposefully not
+/// documented.
+#[doc(hidden)]
+pub trait ReturnUnstubbed<O> {
+    fn unmatched_output(&self) -> O;
+}
+impl<T, E: From<Unstubbed>> ReturnUnstubbed<Result<T, E>> for &Unmatched<Result<T, E>> {
+    #[track_caller]
+    fn unmatched_output(&self) -> Result<T, E> {
+        match self.unstubbed() {
+            Some(unstubbed) => Err(E::from(unstubbed)),
+            None => unmatched(self.error.clone()),
+        }
+    }
+}
+
+/// Panics with the error.
+///
+/// Exposed so generated code can use it but purposefully not
+/// documented.
+#[doc(hidden)]
+pub trait PanicUnmatched<O> {
+    fn unmatched_output(&self) -> O;
+}
+
+impl<O> PanicUnmatched<O> for Unmatched<O> {
+    #[track_caller]
+    fn unmatched_output(&self) -> O {
+        unmatched(self.error.clone())
+    }
+}
@@ -1,4 +1,5 @@
 #![allow(clippy::needless_doctest_main)]
+#![allow(clippy::test_attr_in_doctest)]
 
 //! A library to create [mocks] out of structs.
 //!
@@ -243,14 +244,47 @@
 //! * Generic struct methods
 //! * Methods with pointer self types (e.g., `self: Rc<Self>`)
 //! * Methods in external modules
-//! * Support for `Debug`, `Default`, `Clone`, `Send`, and `Sync`
-//!   derive/auto traits.
+//! * Support for `Debug`, `Default`, `Clone`, `PartialEq`, `Eq`,
+//!   `Hash`, `PartialOrd`, `Ord`, `Send`, and `Sync` derive/auto
+//!   traits.
+//! * `Serialize` and `Deserialize` derives behind the `serde` feature
 //!
 //! `faux` also provides easy-to-use argument matchers.
 //!
+//! ## Loom
+//!
+//! With the `loom` feature, and when built with `--cfg loom` as
+//! [`loom`] recommends, the stubs of a mock are synchronized using
+//! `loom`'s primitives rather than the standard library's, so calls to
+//! a mock take part in `loom`'s permutations when testing concurrent
+//! code under `loom::model`.
+//!
+//! **Warning:** with `--cfg loom`, every mock panics when it is
+//! created or used outside of `loom::model`. Without it, the `loom`
+//! feature has no effect, so builds with `--all-features` are not
+//! affected.
+//!
+//! [`Gate`] and [`wait_for!`] still block on the standard library's
+//! primitives so they should not be used within `loom::model`.
+//!
+//! ```toml
+//! [features]
+//! loom = ["faux/loom"]
+//!
+//! [dev-dependencies]
+//! faux = "*"
+//! ```
+//!
+//! ```sh
+//! RUSTFLAGS="--cfg loom" cargo test --features loom --release
+//! ```
+//!
+//! [`loom`]: https://docs.rs/loom
+//!
 //! [mocks]: https://martinfowler.com/articles/mocksArentStubs.html
 
 pub mod matcher;
+pub mod wait;
 pub mod when;
 
 /// Transforms a struct into a mockable version of itself.
@@ -270,7 +304,10 @@ pub mod when;
 /// transformed version.
 ///
 /// Only methods within `impl` blocks tagged by
-/// [`#[methods]`](methods) may use the struct's fields.
+/// [`#[methods]`](methods) may use the struct's fields directly. Other
+/// code may reach them through the generated `faux_real` and
+/// `faux_real_mut` methods, which return the real instance or `None`
+/// for mocks. See [Field access](#field-access).
 ///
 /// # Examples
 ///
@@ -364,6 +401,100 @@ pub mod when;
 /// * `#[create(self_type = "Arc")]`
 /// * `#[create(self_type = "Box")]`
 ///
+/// ## cfg
+///
+/// Only transforms the struct when the given configuration predicate
+/// is true. Otherwise, the struct is left untouched so builds outside
+/// of that configuration have no overhead.
+///
+/// If `cfg` is set, it should be set to the same predicate in all
+/// [`#[methods]`](methods) for this struct.
+///
+/// Unlike `#[cfg_attr(predicate, faux::create)]`, the attribute is
+/// still expanded when the predicate does not hold, so `faux` must be
+/// in `[dependencies]` rather than `[dev-dependencies]`:
+///
+/// ```toml
+/// [dependencies]
+/// faux = "^0.1"
+/// ```
+///
+/// ### Examples
+///
+/// ```
+/// #[faux::create(cfg = "test")]
+/// pub struct MyStruct {
+///     a: i32,
+/// }
+///
+/// #[faux::methods(cfg = "test")]
+/// impl MyStruct {
+///     pub fn get(&self) -> i32 {
+///         self.a
+///     }
+/// }
+///
+/// # fn main() {
+/// // doctests are not compiled with `cfg(test)`
+/// let real = MyStruct { a: 3 };
+/// assert_eq!(real.get(), 3);
+/// # }
+/// ```
+///
+/// # Serde
+///
+/// With the `serde` feature enabled, `#[derive(Serialize)]`,
+/// `#[derive(Deserialize)]` and any `#[serde(...)]` attributes on the
+/// tagged struct are applied to the real struct and the mockable
+/// struct forwards to it:
+///
+/// * Deserializing always creates a real instance.
+/// * Serializing a real instance serializes its fields as usual.
+/// * Serializing a mock serializes the instance returned by a stubbed
+///   `serialize`, e.g., `faux::when!(<my_mock as
+///   Serialize>.serialize).then(|_| MyStruct::new())`. If `serialize` was never stubbed, or the
+///   stub returned another mock, serializing fails with an error.
+///
+/// # Field access
+///
+/// The struct's fields are moved into a hidden struct, so code
+/// outside of [`#[methods]`](methods) cannot use `self.field`. Use
+/// `faux_real` (or `faux_real_mut`) to get the real instance
+/// instead. It returns `None` for mocks so such code fails clearly
+/// rather than reaching into a mock.
+///
+/// The returned reference is to the real instance as stored by
+/// [`self_type`](#self_type), e.g., `&Rc<_>` for `self_type = "Rc"`.
+///
+/// ```
+/// #[faux::create]
+/// pub struct MyStruct {
+///     a: i32,
+/// }
+///
+/// #[faux::methods]
+/// impl MyStruct {
+///     pub fn new(a: i32) -> Self {
+///         MyStruct { a }
+///     }
+/// }
+///
+/// // not tagged by #[faux::methods]
+/// impl std::fmt::Display for MyStruct {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         match self.faux_real() {
+///             Some(real) => write!(f, "MyStruct({})", real.a),
+///             None => f.write_str("MyStruct(mock)"),
+///         }
+///     }
+/// }
+///
+/// # fn main() {
+/// assert_eq!(MyStruct::new(3).to_string(), "MyStruct(3)");
+/// assert_eq!(MyStruct::faux().to_string(), "MyStruct(mock)");
+/// # }
+/// ```
+///
 /// [`cargo-expand`]: https://github.com/dtolnay/cargo-expand
 ///
 pub use faux_macros::create;
@@ -373,8 +504,13 @@ pub use faux_macros::create;
 ///
 /// Mockable methods can be mocked using [`when!`].
 ///
-/// Associated functions and private methods cannot be mocked. Calls
-/// to them are proxied to the real implementation.
+/// Associated functions cannot be mocked. Calls to them are proxied
+/// to the real implementation.
+///
+/// Methods with any visibility may be mocked. The generated stubbing
+/// helpers have the same visibility as their method, so a private
+/// method may only be stubbed from within its module and its child
+/// modules, e.g., a `tests` module.
 ///
 /// # Requirements
 ///
@@ -546,8 +682,115 @@ pub use faux_macros::create;
 /// mockable. `self_type` does not specify what types of receivers can
 /// be mocked, but how `faux` stores the instances internally.
 ///
+/// ## unstubbed
+///
+/// Tells the attribute what mock instances do when a method is called
+/// but was never stubbed.
+///
+/// By default, the method panics. If set to `"err"`, methods that
+/// return a `Result` instead return an `Err` created from an
+/// [`Unstubbed`] error if their error type implements
+/// `From<faux::Unstubbed>`.
+///
+/// Methods that do not return a `Result`, or whose error type does
+/// not implement `From<faux::Unstubbed>`, still panic. Calls that none
+/// of the method's stubs matched also still panic; see
+/// [`unmatched`](#unmatched).
+///
+/// ### Examples
+///
+/// ```
+/// use std::io;
+///
+/// #[faux::create]
+/// pub struct Connection {}
+///
+/// #[faux::methods(unstubbed = "err")]
+/// impl Connection {
+///     // `io::Error` implements `From<faux::Unstubbed>`
+///     pub fn send(&self, data: &[u8]) -> io::Result<usize> {
+///         /* implementation code */
+///         # Ok(data.len())
+///     }
+/// }
+///
+/// # fn main() {
+/// let connection = Connection::faux();
+/// assert!(connection.send(&[1, 2, 3]).is_err());
+/// # }
+/// ```
+///
+/// ### Allowed values:
+/// * `#[methods(unstubbed = "panic")]` (default)
+/// * `#[methods(unstubbed = "err")]`
+///
+/// ## unmatched
+///
+/// Tells the attribute what mock instances do when a method is called
+/// but none of its stubs could handle the call, e.g., because the
+/// arguments did not match or the stubs were exhausted.
+///
+/// By default, the method panics with an [`InvocationError`]. If set
+/// to `"err"`, methods that return a `Result` instead return an `Err`
+/// created from the [`InvocationError`] if their error type
+/// implements `From<faux::InvocationError>`. Methods that were never
+/// stubbed are handled by [`unstubbed`](#unstubbed) instead.
+///
+/// ### Examples
+///
+/// ```
+/// #[derive(Clone, Debug)]
+/// pub struct Error(String);
+///
+/// impl From<faux::InvocationError> for Error {
+///     fn from(error: faux::InvocationError) -> Self {
+///         Error(error.to_string())
+///     }
+/// }
+///
+/// #[faux::create]
+/// pub struct Connection {}
+///
+/// #[faux::methods(unmatched = "err")]
+/// impl Connection {
+///     pub fn send(&self, data: u32) -> Result<(), Error> {
+///         /* implementation code */
+///         # Ok(())
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut connection = Connection::faux();
+/// faux::when!(connection.send(1)).then_return(Ok(()));
+///
+/// assert!(connection.send(1).is_ok());
+/// assert!(connection.send(2).is_err());
+/// # }
+/// ```
+///
+/// ### Allowed values:
+/// * `#[methods(unmatched = "panic")]` (default)
+/// * `#[methods(unmatched = "err")]`
+///
+/// ## cfg
+///
+/// Only transforms the methods when the given configuration
+/// predicate is true. Otherwise, the `impl` block is left untouched.
+///
+/// It should use the same predicate as the
+/// [`#[create(cfg = ...)]`](create#cfg) of the struct.
+///
+/// Unlike `#[cfg_attr(predicate, faux::methods)]`, the attribute is
+/// still expanded when the predicate does not hold, so `faux` must be
+/// in `[dependencies]` rather than `[dev-dependencies]`.
+///
 /// # Panics
 ///
+/// Calls to a mock that no stub can handle panic with an
+/// [`InvocationError`]. Use [`on_unmatched!`] to also receive these
+/// errors, e.g., when the panic happens in a thread or an async task
+/// where it may be swallowed.
+///
 /// ## Non-stubbed methods
 ///
 /// ```should_panic
@@ -699,13 +942,225 @@ pub use faux_macros::create;
 /// ## Paths in types
 ///
 /// `#[methods]` can be added to blocks of the form `impl
-/// path::to::Type` as long as the path does not contain the `super`
-/// or `crate` keywords. If it does, use the [`path`](#path) argument
-/// to explicitly specify the path.
+/// path::to::Type`, including paths that start with the `crate` or
+/// `super` keywords such as `impl crate::db::Pool`. Paths with these
+/// keywords may not be combined with the [`path`](#path) argument.
+///
+/// ```
+/// mod db {
+///     #[faux::create]
+///     pub struct Pool {
+///         size: usize,
+///     }
+///
+///     mod impls {
+///         #[faux::methods]
+///         impl super::Pool {
+///             pub fn new(size: usize) -> super::Pool {
+///                 super::Pool { size }
+///             }
+///
+///             pub fn size(&self) -> usize {
+///                 self.size
+///             }
+///         }
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut pool = db::Pool::faux();
+/// faux::when!(pool.size).then_return(3);
+/// assert_eq!(pool.size(), 3);
+/// # }
+/// ```
+///
+/// ## Drop
+///
+/// `#[methods]` may be added to an `impl Drop` block. The drop logic
+/// only runs for real instances as mocks have nothing to clean
+/// up. Use [`drop_handle!`] to verify that a mock instance was
+/// dropped.
+///
+/// ```
+/// #[faux::create]
+/// pub struct Connection {
+///     id: u32,
+/// }
+///
+/// #[faux::methods]
+/// impl Drop for Connection {
+///     fn drop(&mut self) {
+///         /* implementation code */
+///     }
+/// }
+///
+/// # fn main() {
+/// let connection = Connection::faux();
+/// let handle = faux::drop_handle!(connection);
+/// drop(connection);
+/// faux::verify_dropped(&handle);
+/// # }
+/// ```
+///
+/// ## Async methods
+///
+/// `async fn`s, including those in trait impls, are stubbed with the
+/// output of the future rather than the future itself. Methods that
+/// return `impl Future<Output = T>` or a boxed future, i.e.,
+/// `Pin<Box<dyn Future<Output = T>>>` or `BoxFuture<'_, T>`, are
+/// treated the same way so they are also stubbed with a `T`. This
+/// includes the methods that `#[async_trait]` expands to, whether it
+/// expands before or after `#[methods]`. Use [`When::then_async`] to
+/// stub them with a future instead. The mocked future is `Send` as
+/// long as the arguments and the output are.
+///
+/// `async fn` and `impl Future` in traits need Rust 1.75 or newer.
+///
+/// ```
+/// use std::future::Future;
+///
+/// #[faux::create]
+/// pub struct Service {}
+///
+/// #[faux::methods]
+/// impl Service {
+///     pub fn fetch(&self, id: u32) -> impl Future<Output = String> + Send {
+///         /* implementation code */
+///         # async { panic!() }
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut service = Service::faux();
+/// faux::when!(service.fetch).then(|id| format!("fetched {id}"));
+///
+/// let fetched = futures::executor::block_on(service.fetch(3));
+/// assert_eq!(fetched, "fetched 3");
+/// # }
+/// ```
 ///
 /// [receiver]: https://doc.rust-lang.org/reference/items/associated-items.html#methods
 pub use faux_macros::methods;
 
+/// Runs a method's real implementation even on mock instances.
+///
+/// Methods in an `impl` block tagged by [`#[methods]`](methods) may
+/// be marked with `#[faux::real]`. Their body runs as written for
+/// both real and mock instances, with `self` being the mockable
+/// struct, so any methods it calls are stubbed for mocks. This is
+/// useful for methods that are compositions of other methods, as only
+/// the methods they call need to be stubbed.
+///
+/// The body may not access the struct's fields directly since mocks
+/// do not have any. Methods marked with `#[faux::real]` cannot be
+/// stubbed.
+///
+/// The attribute must be written as `#[faux::real]`. A bare `#[real]`
+/// is left alone, even if imported from `faux`, as it may belong to
+/// another crate.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(Clone, Debug, PartialEq)]
+/// pub enum Status {
+///     Ready,
+///     Busy,
+/// }
+///
+/// #[faux::create]
+/// pub struct Worker {
+///     status: Status,
+/// }
+///
+/// #[faux::methods]
+/// impl Worker {
+///     pub fn status(&self) -> Status {
+///         self.status.clone()
+///     }
+///
+///     #[faux::real]
+///     pub fn is_ready(&self) -> bool {
+///         self.status() == Status::Ready
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut worker = Worker::faux();
+/// faux::when!(worker.status).then_return(Status::Busy);
+/// assert!(!worker.is_ready());
+/// # }
+/// ```
+pub use faux_macros::real;
+
+/// Creates a mockable wrapper for a struct from another crate.
+///
+/// [`#[create]`](create) and [`#[methods]`](methods) need to be added
+/// to the struct's definition, which is not possible for crates that
+/// do not export their mocks. `mock_foreign!` instead creates a
+/// local struct of the same name that wraps the foreign struct. Real
+/// instances call the foreign methods while mock instances are
+/// stubbed as usual using [`when!`].
+///
+/// The wrapper implements `From` for the foreign struct so code
+/// using it only needs to change an import and wrap the foreign
+/// instance with `.into()`.
+///
+/// The listed methods are declared without a body. Methods listed in
+/// a bare `impl` block call the inherent foreign method of the same
+/// name, while methods listed in an `impl Trait` block call the
+/// foreign implementation of that trait. Methods returning `Self`,
+/// `Option<Self>`, or `Result<Self, E>` wrap the returned foreign
+/// instance. Only `self`, `&self`, and `&mut self` receivers are
+/// supported.
+///
+/// # Examples
+///
+/// ```
+/// // pretend this is a crate that does not export mocks
+/// mod sdk {
+///     pub struct Client { /* fields */ }
+///
+///     #[derive(Debug)]
+///     pub struct Error;
+///
+///     impl Client {
+///         pub fn connect(url: &str) -> Result<Self, Error> {
+///             /* implementation code */
+///             # Ok(Client {})
+///         }
+///
+///         pub fn get(&self, key: &str) -> Result<Vec<u8>, Error> {
+///             /* implementation code */
+///             # Ok(key.as_bytes().to_vec())
+///         }
+///     }
+/// }
+///
+/// faux::mock_foreign! {
+///     pub struct sdk::Client;
+///     impl {
+///         fn connect(url: &str) -> Result<Self, sdk::Error>;
+///         fn get(&self, key: &str) -> Result<Vec<u8>, sdk::Error>;
+///     }
+/// }
+///
+/// // uses the local `Client` rather than `sdk::Client`
+/// fn fetch(client: &Client) -> Vec<u8> {
+///     client.get("key").unwrap()
+/// }
+///
+/// # fn main() {
+/// let real = Client::connect("some_url").unwrap();
+/// assert_eq!(fetch(&real), b"key".to_vec());
+///
+/// let mut mock = Client::faux();
+/// faux::when!(mock.get).then(|_| Ok(vec![1, 2, 3]));
+/// assert_eq!(fetch(&mock), vec![1, 2, 3]);
+/// # }
+/// ```
+pub use faux_macros::mock_foreign;
+
 /// Creates a [`When`] instance to stub a specific method in a struct.
 ///
 /// Callers may specify argument matchers to limit the arguments for
@@ -759,19 +1214,25 @@ pub use faux_macros::methods;
 /// An argument mismatch would look something like:
 ///
 /// ```term
-/// thread 'main' panicked at 'failed to call stub on 'Foo::some_method':
+/// thread 'main' panicked at '`Foo::some_method` had no suitable stubs. The closest stub (#1) failed because:
 /// ✗ Arguments did not match
 ///   Expected: [8, 9]
-///   Actual:   [1, 1]
+///   Actual:   [8, 1]
 ///
-///   Argument 0:
-///     Expected: 8
-///     Actual:   1
 ///   Argument 1:
 ///     Expected: 9
 ///     Actual:   1
+///
+/// Other stubs failed because:
+///   ✗ #0: Arguments 0, 1 did not match
 /// ```
 ///
+/// When a method has multiple stubs, the closest stub is the one with
+/// the fewest arguments that failed to match. Stubs are numbered in
+/// the order they were added. Arguments whose expected or actual
+/// value span multiple lines when pretty-printed (e.g., structs) are
+/// followed by a line-level diff of both values.
+///
 /// # Argument Matchers
 ///
 /// Argument matchers are specified by passing them to `when!`:
@@ -885,6 +1346,60 @@ pub use faux_macros::methods;
 ///
 /// ```
 ///
+/// # Trait methods
+///
+/// Trait methods may be stubbed by their name alone in the module of
+/// their `impl` block and its child modules, e.g., `mod tests`, as
+/// long as the struct has no other method of the same name. Inherent
+/// methods take precedence over trait methods of the same name.
+///
+/// Elsewhere, or to select one of multiple methods of the same name,
+/// use `<receiver as Trait>`. Only the last segment of each path is
+/// used, so `Trait` does not need to be in scope and may be written
+/// with or without its module, e.g., `<receiver as io::Read>`.
+/// Generic arguments are compared the same way, e.g., `<receiver as
+/// From<path::Ty>>` selects `impl From<Ty>`, but type aliases are not
+/// resolved so the arguments must name the same types as the `impl`.
+/// Traits that share a name are told apart by importing one of them
+/// under another name, e.g., `use other::Trait as OtherTrait;`, in
+/// both the `impl` and the stub.
+///
+/// ```
+/// use std::io::{self, Read};
+///
+/// pub trait MyReader {
+///     fn read(&self) -> String;
+/// }
+///
+/// #[faux::create]
+/// pub struct Source {}
+///
+/// #[faux::methods]
+/// impl Read for Source {
+///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+///         /* implementation code */
+///         # panic!()
+///     }
+/// }
+///
+/// #[faux::methods]
+/// impl MyReader for Source {
+///     fn read(&self) -> String {
+///         /* implementation code */
+///         # panic!()
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut source = Source::faux();
+/// faux::when!(<source as Read>.read(_)).then(|buf| Ok(buf.len()));
+/// faux::when!(<source as MyReader>.read).then_return("hello".to_owned());
+///
+/// assert_eq!(Read::read(&mut source, &mut [0; 3]).unwrap(), 3);
+/// assert_eq!(MyReader::read(&source), "hello");
+/// # }
+/// ```
+///
 /// [`When`]: struct.When.html
 /// [`any()`]: matcher/fn.any.html
 /// [`eq_against({expr})`]: matcher/fn.eq_against.html
@@ -898,14 +1413,140 @@ pub use faux_macros::when;
 #[doc(inline)]
 pub use when::When;
 
+/// Waits for calls to a stubbed method, e.g., from another thread.
+///
+/// `wait_for!(receiver.method(args))` blocks the current thread until
+/// the method is called. `wait_for!(async receiver.method(args))`
+/// instead returns a future to `.await`. Waiters are woken up as
+/// calls are made so no polling or sleeping is involved.
+///
+/// The method must have been stubbed before waiting.
+///
+/// Two optional arguments may follow the method:
+///
+/// * `times = {expr}`: how many calls to wait for. Defaults to 1.
+/// * `timeout = {expr}`: a [`Duration`] to give up after. Defaults
+///   to 5 seconds.
+///
+/// Arguments use the same [matcher syntax](macro@when#matcher-syntax) as
+/// [`when!`]. Without matchers, or if every matcher is `_`, calls
+/// made before waiting are also counted. Otherwise, only the matching
+/// calls made after the wait started are counted, as `faux` does not
+/// keep the arguments of earlier calls. The wait starts when
+/// `wait_for!` is evaluated, even if the future is polled later.
+///
+/// # Panics
+///
+/// Panics if the method is not called enough times before the
+/// timeout. The panic message reports how many calls matched, the
+/// calls that did not, and how many earlier calls were not checked
+/// against the matchers.
+///
+/// # Examples
+///
+/// ```
+/// #[faux::create]
+/// pub struct Publisher {}
+///
+/// #[faux::methods]
+/// impl Publisher {
+///     pub fn publish(&self, topic: &str) {
+///         /* implementation code */
+///         # panic!()
+///     }
+/// }
+///
+/// # fn main() {
+/// use std::{sync::Arc, time::Duration};
+///
+/// let mut publisher = Publisher::faux();
+/// faux::when!(publisher.publish).then_return(());
+///
+/// let publisher = Arc::new(publisher);
+/// for _ in 0..3 {
+///     let publisher = publisher.clone();
+///     std::thread::spawn(move || publisher.publish("news"));
+/// }
+///
+/// faux::wait_for!(
+///     publisher.publish(_),
+///     times = 3,
+///     timeout = Duration::from_secs(1),
+/// );
+/// # }
+/// ```
+///
+/// [`Duration`]: std::time::Duration
+pub use faux_macros::wait_for;
+
+#[doc(inline)]
+pub use wait::WaitFor;
+
+/// Returns the [`Calls`] made to a stubbed method.
+///
+/// `calls!(receiver.method)` takes a snapshot of the calls made to
+/// the method so far, counted by their completion status. Use
+/// `<receiver as Trait>.method` to select a trait method as in
+/// [`when!`](macro@when).
+///
+/// # Examples
+///
+/// ```
+/// #[faux::create]
+/// pub struct Service {}
+///
+/// #[faux::methods]
+/// impl Service {
+///     pub async fn fetch(&self, id: u32) -> String {
+///         /* implementation code */
+///         # panic!()
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut service = Service::faux();
+/// faux::when!(service.fetch).then(|id| id.to_string());
+///
+/// futures::executor::block_on(service.fetch(1));
+///
+/// let calls = faux::calls!(service.fetch);
+/// assert_eq!(calls.len(), 1);
+/// assert_eq!(calls.completed(), 1);
+/// assert_eq!(calls.cancelled(), 0);
+/// # }
+/// ```
+pub use faux_macros::calls;
+
 #[doc(inline)]
 pub use matcher::ArgMatcher;
 
+mod calls;
+mod drop_handle;
+mod gate;
+mod invocation_error;
 mod mock;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod sync;
 
-use core::fmt;
-use std::fmt::Formatter;
-use std::sync::Arc;
+pub use calls::Calls;
+pub use drop_handle::{verify_dropped, DropHandle};
+pub use gate::Gate;
+#[doc(hidden)]
+pub use invocation_error::{
+    unmatched, PanicUnmatched, ReturnError, ReturnInvocationError, ReturnUnstubbed, Unmatched,
+};
+pub use invocation_error::{InvocationError, StubError, UnmatchedHandler, Unstubbed};
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use {serde, serde_impls::serialize_stub};
+
+use std::{
+    cmp::Ordering,
+    future::Future,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
 /// What all mockable structs get transformed into.
 ///
@@ -936,8 +1577,22 @@ use std::sync::Arc;
 /// implements_default(3);
 /// implements_default(faux::MaybeFaux::Real(3));
 /// ```
+///
+/// ```
+/// use std::hash::Hash;
+///
+/// fn implements_data_traits<T: Eq + Hash + Ord>(_: T) {}
+///
+/// implements_data_traits(3);
+/// implements_data_traits(faux::MaybeFaux::Real(3));
+/// ```
+///
+/// Real instances are compared by their value while mock instances
+/// are compared by their identity; a mock is only equal to itself and
+/// its clones. Real instances are never equal to mock instances and
+/// are always ordered before them.
 #[doc(hidden)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum MaybeFaux<T> {
     Real(T),
     Faux(Faux),
@@ -963,13 +1618,49 @@ impl<T> MaybeFaux<T> {
 #[doc(hidden)]
 #[derive(Clone, Debug)]
 pub struct Faux {
-    store: Arc<mock::Store<'static>>,
+    store: sync::Arc<mock::Store<'static>>,
+    // shared by the instances that share the store to tell when they
+    // were all dropped, which loom's `Arc` cannot do
+    instances: Arc<()>,
+    // shared by the instances that share the store to report the
+    // errors of any of them
+    handlers: Arc<invocation_error::Handlers>,
+}
+
+// mocks are compared and hashed by identity: a mock instance is only
+// equal to itself and its clones as they share the same store
+impl PartialEq for Faux {
+    fn eq(&self, other: &Self) -> bool {
+        sync::Arc::ptr_eq(&self.store, &other.store)
+    }
+}
+
+impl Eq for Faux {}
+
+impl Hash for Faux {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(sync::Arc::as_ptr(&self.store), state)
+    }
+}
+
+impl PartialOrd for Faux {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Faux {
+    fn cmp(&self, other: &Self) -> Ordering {
+        sync::Arc::as_ptr(&self.store).cmp(&sync::Arc::as_ptr(&other.store))
+    }
 }
 
 impl Faux {
     pub fn new(name: &'static str) -> Self {
         Faux {
-            store: Arc::new(mock::Store::new(name)),
+            store: sync::Arc::new(mock::Store::new(name)),
+            instances: Arc::new(()),
+            handlers: Arc::default(),
         }
     }
 
@@ -978,7 +1669,28 @@ impl Faux {
     /// Returns `None` if the store is being shared by multiple mock
     /// instances. This occurs when cloning a mock instance.
     pub(crate) fn unique_store(&mut self) -> Option<&mut mock::Store<'static>> {
-        Arc::get_mut(&mut self.store)
+        sync::Arc::get_mut(&mut self.store)
+    }
+
+    #[doc(hidden)]
+    /// Attempt to call a stub for a given function and input.
+    ///
+    /// Kept for code generated by earlier versions of
+    /// `#[faux::methods]`, which call it within an `unsafe` block. See
+    /// [`call`](Faux::call).
+    ///
+    /// # Safety
+    ///
+    /// Do *NOT* call this function directly.
+    /// This should only be called by the generated code from #[faux::methods]
+    pub unsafe fn call_stub<R, I, O>(
+        &self,
+        id: fn(R, I) -> O,
+        fn_name: &'static str,
+        input: I,
+        generics: &'static str,
+    ) -> Result<O, InvocationError> {
+        self.call(id, fn_name, input, generics)
     }
 
     #[doc(hidden)]
@@ -990,66 +1702,91 @@ impl Faux {
     /// matcher will be activated and its output returned. If one
     /// cannot be found an error is returned.
     ///
-    /// # Safety
+    /// Safe to call as the mock is looked up with the same `id` that
+    /// stubbed it, which determines its types, and the names of its
+    /// types are checked before it is used; see the docs on
+    /// `mock::Store`.
     ///
     /// Do *NOT* call this function directly.
     /// This should only be called by the generated code from #[faux::methods]
-    pub unsafe fn call_stub<R, I, O>(
+    pub fn call<R, I, O>(
         &self,
         id: fn(R, I) -> O,
         fn_name: &'static str,
         input: I,
         generics: &'static str,
     ) -> Result<O, InvocationError> {
-        let mock = self.store.get(id, fn_name, generics)?;
-        mock.call(input).map_err(|stub_error| InvocationError {
-            fn_name: mock.name(),
-            struct_name: self.store.struct_name,
-            generics,
-            stub_error,
-        })
+        let (output, mock) = self.output(id, fn_name, input, generics)?;
+        let output = match output {
+            mock::Output::Ready(output) => output,
+            mock::Output::Gated(gate, output) => {
+                gate.pass_blocking();
+                output
+            }
+            mock::Output::Pending(_) => panic!(
+                "faux: '{}::{}' was stubbed using `then_async` but it is not an async method",
+                self.store.struct_name, fn_name
+            ),
+        };
+        mock.complete();
+        Ok(output)
     }
-}
-
-pub struct InvocationError {
-    struct_name: &'static str,
-    fn_name: &'static str,
-    generics: &'static str,
-    stub_error: mock::InvocationError,
-}
 
-impl fmt::Display for InvocationError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let generics = if self.generics.is_empty() {
-            String::new()
-        } else {
-            format!("<{}>", self.generics)
-        };
-        match &self.stub_error {
-            mock::InvocationError::NeverStubbed => {
-                write!(
-                    f,
-                    "`{}::{}{}` was called but never stubbed",
-                    self.struct_name, self.fn_name, generics
-                )
-            }
-            mock::InvocationError::Stub(errors) => {
-                writeln!(
-                    f,
-                    "`{}::{}{}` had no suitable stubs. Existing stubs failed because:",
-                    self.struct_name, self.fn_name, generics
-                )?;
-                let mut errors = errors.iter();
-                if let Some(e) = errors.next() {
-                    f.write_str("✗ ")?;
-                    fmt::Display::fmt(e, f)?;
+    #[doc(hidden)]
+    /// Analog of [`call`](Faux::call) for async methods
+    ///
+    /// The returned future resolves to the output of the stub, awaiting
+    /// it first if it was stubbed using `then_async`.
+    ///
+    /// Do *NOT* call this function directly.
+    /// This should only be called by the generated code from #[faux::methods]
+    pub fn call_async<R, I, O>(
+        &self,
+        id: fn(R, I) -> O,
+        fn_name: &'static str,
+        input: I,
+        generics: &'static str,
+    ) -> Result<impl Future<Output = O>, InvocationError> {
+        let (output, mock) = self.output(id, fn_name, input, generics)?;
+        let call = mock.track();
+        Ok(async move {
+            // the call is cancelled if dropped before completing
+            let polling = call.poll();
+            let output = match output {
+                mock::Output::Ready(output) => output,
+                mock::Output::Pending(future) => future.await,
+                mock::Output::Gated(gate, output) => {
+                    gate.pass().await;
+                    output
                 }
-                errors.try_for_each(|e| {
-                    f.write_str("\n\n✗ ")?;
-                    fmt::Display::fmt(e, f)
-                })
-            }
+            };
+            polling.complete();
+            output
+        })
+    }
+
+    fn output<R, I, O>(
+        &self,
+        id: fn(R, I) -> O,
+        fn_name: &'static str,
+        input: I,
+        generics: &'static str,
+    ) -> Result<(mock::Output<'static, O>, &mock::Mock<'static, I, O>), InvocationError> {
+        // Safety: the generated code always pairs `id` with the same
+        // name, see the docs on `mock::Store`
+        let mock = unsafe { self.store.get(id, fn_name, generics) };
+        let output = mock.and_then(|mock| {
+            let output = mock
+                .call(self.store.struct_name, input)
+                .map_err(|stub_error| {
+                    InvocationError::new(self.store.struct_name, mock.name(), generics, stub_error)
+                })?;
+            Ok((output, mock))
+        });
+        if let Err(error) = &output {
+            self.handlers.report(error);
         }
+        output
     }
 }
 
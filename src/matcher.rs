@@ -11,11 +11,13 @@ mod any;
 mod eq;
 mod from_fn;
 mod invocation_matcher;
+mod mismatch;
 
 pub use any::any;
 pub use eq::{eq, eq_against};
 pub use from_fn::from_fn;
 pub use invocation_matcher::{AnyInvocation, InvocationMatcher};
+pub use mismatch::{ArgMismatch, Mismatch};
 
 use std::fmt::{self, Formatter};
 
@@ -30,7 +32,10 @@ use std::fmt::{self, Formatter};
 ///
 /// You may define your own matcher for special use cases. The
 /// [`fmt::Display`] implementation is used by [`InvocationMatcher`]
-/// to display the expectation when any arguments failed to match.
+/// to display the expectation when any arguments failed to match. If
+/// the alternate flag (`{:#}`) is set, consider pretty-printing the
+/// expectation so `faux` can show a line-level diff against the
+/// argument.
 ///
 /// # Examples
 ///
@@ -96,6 +101,8 @@ impl<Arg, AM: ArgMatcher<Arg>> ArgMatcher<&Arg> for RefMatcher<AM> {
 
 impl<AM: fmt::Display> fmt::Display for RefMatcher<AM> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "*{}", self.0)
+        // forward the formatter so alternate (pretty) printing is kept
+        f.write_str("*")?;
+        self.0.fmt(f)
     }
 }
@@ -22,7 +22,11 @@ where
     Expected: fmt::Debug,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.0)
+        if f.alternate() {
+            write!(f, "{:#?}", self.0)
+        } else {
+            write!(f, "{:?}", self.0)
+        }
     }
 }
 
@@ -147,6 +151,10 @@ where
     Expected: fmt::Debug,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "_ == {:?}", self.0)
+        if f.alternate() {
+            write!(f, "_ == {:#?}", self.0)
+        } else {
+            write!(f, "_ == {:?}", self.0)
+        }
     }
 }
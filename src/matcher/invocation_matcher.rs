@@ -1,7 +1,7 @@
 use core::fmt;
 use paste::paste;
 
-use super::ArgMatcher;
+use super::{ArgMatcher, ArgMismatch, Mismatch};
 
 /// Matcher for the invocation of a method.
 ///
@@ -38,6 +38,18 @@ pub trait InvocationMatcher<Args> {
     /// Returns `Err(String)` if any argument fails to match. The
     /// error should detail which arguments failed and why.
     fn matches(&self, args: &Args) -> Result<(), String>;
+
+    /// Returns `Ok(())` when all arguments match.
+    ///
+    /// Returns a [`Mismatch`] if any argument fails to match. Unlike
+    /// [`matches`](InvocationMatcher::matches), the mismatch may
+    /// track which arguments failed, letting `faux` point out the
+    /// closest stub when none of them match an invocation.
+    ///
+    /// Defaults to wrapping the error returned by `matches`.
+    fn check(&self, args: &Args) -> Result<(), Mismatch> {
+        self.matches(args).map_err(Mismatch::new)
+    }
 }
 
 #[doc(hidden)]
@@ -60,14 +72,26 @@ impl InvocationMatcher<()> for () {
 impl<Arg: fmt::Debug, AM: ArgMatcher<Arg>> InvocationMatcher<Arg> for (AM,) {
     /// Succeeds if the argument matches the [`ArgMatcher`].
     fn matches(&self, arg: &Arg) -> Result<(), String> {
+        self.check(arg)
+            .map_err(|mismatch| mismatch.message().to_owned())
+    }
+
+    fn check(&self, arg: &Arg) -> Result<(), Mismatch> {
         if self.0.matches(arg) {
             Ok(())
         } else {
-            Err(format!(
-                "Argument did not match.
+            Err(Mismatch::with_arguments(
+                format!(
+                    "Argument did not match.
 Expected: {}
 Actual:   {:?}",
-                self.0, arg
+                    self.0, arg
+                ),
+                vec![ArgMismatch::new(
+                    0,
+                    format!("{:#}", self.0),
+                    format!("{:#?}", arg),
+                )],
             ))
         }
     }
@@ -91,7 +115,12 @@ macro_rules! tuple {
         paste! {
             impl<$([<A $idx>]: fmt::Debug),+,$([<AM $idx>]: ArgMatcher<[<A $idx>]>),+> InvocationMatcher<($([<A $idx>],)+)> for ($([<AM $idx>],)+) {
                 /// Succeeds if every argument matches its corresponding [`ArgMatcher`].
-                fn matches(&self, ($([<a $idx>]),+): &($([<A $idx>],)+)) -> Result<(), String> {
+                fn matches(&self, args: &($([<A $idx>],)+)) -> Result<(), String> {
+                    self.check(args)
+                        .map_err(|mismatch| mismatch.message().to_owned())
+                }
+
+                fn check(&self, ($([<a $idx>]),+): &($([<A $idx>],)+)) -> Result<(), Mismatch> {
                     let ($([<am $idx>]),+) = &self;
                     let matches = match ($([<am $idx>].matches([<a $idx>])),+) {
                         trues!($($idx),+) => return Ok(()),
@@ -103,6 +132,9 @@ macro_rules! tuple {
                     let actual = [
                         $(format!("{:?}", [<a $idx>])),+
                     ];
+                    let pretty = [
+                        $((format!("{:#}", [<am $idx>]), format!("{:#?}", [<a $idx>]))),+
+                    ];
 
                     let mut widths = [
                         $(expected[$idx].len().max(actual[$idx].len())),+
@@ -133,17 +165,25 @@ macro_rules! tuple {
                         ))
                         .collect();
 
+                    let arguments = pretty
+                        .into_iter()
+                        .zip(matches)
+                        .enumerate()
+                        .filter(|(_, (_, passed))| !passed)
+                        .map(|(pos, ((expected, actual), _))| ArgMismatch::new(pos, expected, actual))
+                        .collect();
+
                     let argument_errors = argument_errors.join("\n");
                     let expected = expected.join(", ");
                     let actual = actual.join(", ");
 
-                    Err(format!("Arguments did not match
+                    Err(Mismatch::with_arguments(format!("Arguments did not match
   Expected: [{}]
   Actual:   [{}]
 
 {}",
                         expected, actual, argument_errors
-                    ))
+                    ), arguments))
                 }
             }
         }
@@ -0,0 +1,174 @@
+use std::fmt::{self, Formatter};
+
+/// Explains why an invocation did not match an
+/// [`InvocationMatcher`](super::InvocationMatcher).
+///
+/// Besides a human readable message, a `Mismatch` may know which
+/// arguments failed to match. `faux` uses this to point out the
+/// closest stub when no stub matches an invocation.
+///
+/// # Examples
+///
+/// ```
+/// use faux::matcher::{self, InvocationMatcher};
+///
+/// let matcher = (matcher::eq(5), matcher::any(), matcher::eq("hi"));
+/// let mismatch = matcher.check(&(3, 4, "bye")).unwrap_err();
+///
+/// let failed: Vec<_> = mismatch
+///     .arguments()
+///     .unwrap()
+///     .iter()
+///     .map(|a| a.position())
+///     .collect();
+/// assert_eq!(failed, vec![0, 2]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    message: String,
+    arguments: Option<Vec<ArgMismatch>>,
+}
+
+/// An argument that did not match its [`ArgMatcher`](super::ArgMatcher).
+#[derive(Debug, Clone)]
+pub struct ArgMismatch {
+    position: usize,
+    expected: String,
+    actual: String,
+}
+
+impl Mismatch {
+    /// Creates a mismatch that does not know which arguments failed.
+    pub fn new(message: impl Into<String>) -> Self {
+        Mismatch {
+            message: message.into(),
+            arguments: None,
+        }
+    }
+
+    /// Creates a mismatch that knows which arguments failed.
+    pub fn with_arguments(message: impl Into<String>, arguments: Vec<ArgMismatch>) -> Self {
+        Mismatch {
+            message: message.into(),
+            arguments: Some(arguments),
+        }
+    }
+
+    /// The message explaining why the invocation did not match.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The arguments that failed to match.
+    ///
+    /// Returns `None` if the matcher does not track individual
+    /// arguments.
+    pub fn arguments(&self) -> Option<&[ArgMismatch]> {
+        self.arguments.as_deref()
+    }
+}
+
+impl ArgMismatch {
+    /// Creates a mismatch for the argument in the given position.
+    ///
+    /// `expected` and `actual` are expected to be in their pretty
+    /// form (i.e., `{:#}` for the matcher and `{:#?}` for the
+    /// argument).
+    pub fn new(position: usize, expected: String, actual: String) -> Self {
+        ArgMismatch {
+            position,
+            expected,
+            actual,
+        }
+    }
+
+    /// The position of the argument in the method signature,
+    /// ignoring the receiver.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The pretty-printed expectation of the argument.
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    /// The pretty-printed value of the argument.
+    pub fn actual(&self) -> &str {
+        &self.actual
+    }
+}
+
+impl fmt::Display for Mismatch {
+    /// Writes the message followed by a line-level diff of every
+    /// argument whose expectation or value spans multiple lines.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+
+        let multiline = self
+            .arguments()
+            .unwrap_or_default()
+            .iter()
+            .filter(|a| a.expected.contains('\n') || a.actual.contains('\n'));
+
+        for argument in multiline {
+            write!(
+                f,
+                "\n\n  Argument {} diff (- expected, + actual):",
+                argument.position
+            )?;
+            let expected: Vec<_> = argument.expected.lines().collect();
+            let actual: Vec<_> = argument.actual.lines().collect();
+            for line in diff(&expected, &actual) {
+                match line {
+                    Line::Same(l) => write!(f, "\n      {}", l)?,
+                    Line::Expected(l) => write!(f, "\n    - {}", l)?,
+                    Line::Actual(l) => write!(f, "\n    + {}", l)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+enum Line<'a> {
+    Same(&'a str),
+    Expected(&'a str),
+    Actual(&'a str),
+}
+
+// line-level diff based on the longest common subsequence
+// debug outputs are small enough that the quadratic table is fine
+fn diff<'a>(expected: &[&'a str], actual: &[&'a str]) -> Vec<Line<'a>> {
+    let (n, m) = (expected.len(), actual.len());
+    let mut lcs = vec![vec![0_usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if expected[i] == actual[j] {
+            lines.push(Line::Same(expected[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(Line::Expected(expected[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Actual(actual[j]));
+            j += 1;
+        }
+    }
+    lines.extend(expected[i..].iter().map(|l| Line::Expected(l)));
+    lines.extend(actual[j..].iter().map(|l| Line::Actual(l)));
+    lines
+}
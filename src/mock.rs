@@ -1,30 +1,127 @@
 pub mod stub;
 
+mod counter;
+mod local;
 mod store;
 mod unchecked;
 
 use std::{
     fmt::{self, Formatter},
-    sync::Mutex,
+    sync::Arc,
 };
 
-pub use self::{store::Store, stub::Stub};
+use crate::{
+    calls::{Call, Calls, Tracker},
+    matcher::InvocationMatcher,
+    sync::{AtomicBool, Mutex, Ordering},
+    wait::Watch,
+};
+
+use self::counter::{Counter, Snapshot};
+
+pub use self::{
+    store::Store,
+    stub::{Output, Stub, StubId},
+};
 
 /// A function mock
 ///
 /// Stores information about a mock, such as its stubs, with its
 /// inputs and output typed.
+///
+/// Calls only lock what they need to, e.g., the matcher of a stub
+/// with argument matchers, so concurrent calls to stubs without
+/// argument matchers or limits that are stubbed with a `Fn` run
+/// without locking.
 pub struct Mock<'stub, I, O> {
     fn_name: &'static str,
-    stubs: Vec<Mutex<Stub<'stub, I, O>>>,
+    stubs: Vec<Stub<'stub, I, O>>,
+    // how many times the mock was called
+    count: Counter,
+    // set once there are watchers so calls skip locking them otherwise
+    watched: AtomicBool,
+    // those waiting for calls, along with the count when they started
+    watchers: Mutex<Vec<Watcher<I>>>,
+    // the completed calls to sync methods
+    completed: Counter,
+    // the calls to async methods answered by a stub, and the calls
+    // whose stub panicked
+    calls: Arc<Tracker>,
 }
 
+/// Watches the calls that pass its matcher, made after its snapshot
+type Watcher<I> = (Box<dyn InvocationMatcher<I> + Send>, Arc<Watch>, Snapshot);
+
 impl<'stub, I, O> Mock<'stub, I, O> {
     /// Creates an empty mock
     pub fn new(fn_name: &'static str) -> Self {
         Self {
             fn_name,
             stubs: vec![],
+            count: Counter::new(),
+            watched: AtomicBool::new(false),
+            watchers: Mutex::new(vec![]),
+            completed: Counter::new(),
+            calls: Arc::default(),
+        }
+    }
+
+    /// Starts watching the invocations that pass the matcher
+    ///
+    /// Invocations prior to watching can only be counted, so they are
+    /// only included if `count_previous` is set.
+    pub(crate) fn watch(
+        &self,
+        matcher: Box<dyn InvocationMatcher<I> + Send>,
+        count_previous: bool,
+    ) -> Arc<Watch> {
+        let mut watchers = self.watchers.lock().unwrap();
+        // flagged before reading the count: any call not included in
+        // the count is guaranteed to see the flag (see `record`)
+        self.watched.store(true, Ordering::SeqCst);
+        let since = self.count.snapshot();
+        let watch = Arc::new(Watch::new(since.total(), count_previous));
+        watchers.push((matcher, watch.clone(), since));
+        watch
+    }
+
+    /// Stops watching invocations for the given watch
+    pub(crate) fn unwatch(&self, watch: &Arc<Watch>) {
+        let mut watchers = self.watchers.lock().unwrap();
+        watchers.retain(|(_, w, _)| !Arc::ptr_eq(w, watch));
+        // calls go back to skipping the watchers once none are left
+        if watchers.is_empty() {
+            self.watched.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// Starts tracking the status of a call to an async method
+    pub(crate) fn track(&self) -> Call {
+        self.calls.call()
+    }
+
+    /// Records a completed call to a sync method
+    pub(crate) fn complete(&self) {
+        self.completed.increment();
+    }
+
+    /// The calls answered by a stub
+    pub(crate) fn calls(&self) -> Calls {
+        // a method is either sync or async so at most one of them has calls
+        self.calls.calls(self.completed.total())
+    }
+
+    fn record(&self, input: &I) {
+        let call = self.count.increment();
+        if !self.watched.load(Ordering::SeqCst) {
+            return;
+        }
+
+        for (matcher, watch, since) in self.watchers.lock().unwrap().iter() {
+            // earlier calls were already counted when watching started
+            if !since.includes(call) {
+                watch.record(matcher.check(input));
+            }
         }
     }
 
@@ -35,16 +132,30 @@ impl<'stub, I, O> Mock<'stub, I, O> {
     /// inputs. The stubs are checked in reverse insertion order such
     /// that the last inserted stub is the first attempted
     /// one. Returns an error if no stub is found for the given input.
-    pub fn call(&self, mut input: I) -> Result<O, InvocationError> {
+    ///
+    /// The stub runs without holding any locks so it may call back
+    /// into the mock. Panics from the stub are reported along with
+    /// the index of the stub and the name of the mocked struct.
+    pub fn call(
+        &self,
+        struct_name: &'static str,
+        input: I,
+    ) -> Result<Output<'stub, O>, InvocationError> {
+        self.record(&input);
         let mut errors = vec![];
 
-        for stub in self.stubs.iter().rev() {
-            match stub.lock().unwrap().call(input) {
-                Err((i, e)) => {
-                    errors.push(e);
-                    input = i
+        for (index, stub) in self.stubs.iter().enumerate().rev() {
+            let id = StubId {
+                struct_name,
+                fn_name: self.fn_name,
+                index,
+            };
+            match stub.claim(&input, id) {
+                Err(e) => errors.push((index, e)),
+                Ok(invocation) => {
+                    let _answering = self.calls.answer();
+                    return Ok(invocation.invoke(input, id));
                 }
-                Ok(o) => return Ok(o),
             }
         }
 
@@ -57,7 +168,7 @@ impl<'stub, I, O> Mock<'stub, I, O> {
 
     /// Adds a new stub for the mocked function
     pub fn add_stub(&mut self, stub: Stub<'stub, I, O>) {
-        self.stubs.push(Mutex::new(stub))
+        self.stubs.push(stub)
     }
 
     pub fn name(&self) -> &'static str {
@@ -65,10 +176,12 @@ impl<'stub, I, O> Mock<'stub, I, O> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum InvocationError {
     NeverStubbed,
-    Stub(Vec<stub::Error>),
+    /// The errors of every stub paired with the order in which the
+    /// stub was added, sorted in the order the stubs were attempted
+    Stub(Vec<(usize, stub::Error)>),
 }
 
 impl<I, O> fmt::Debug for Mock<'_, I, O> {
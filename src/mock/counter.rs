@@ -0,0 +1,78 @@
+use crate::sync::{AtomicUsize, Ordering};
+
+/// How many counts are spread across threads
+const SHARDS: usize = 16;
+
+/// Counts from many threads without contending over a single atomic
+///
+/// Each thread counts in its own shard so concurrent calls do not
+/// bounce the same cache line between cores.
+pub(super) struct Counter {
+    shards: Box<[Shard]>,
+}
+
+// padded so shards do not share a cache line
+#[repr(align(64))]
+struct Shard(AtomicUsize);
+
+/// Where a count landed within a [`Counter`]
+#[derive(Clone, Copy)]
+pub(super) struct Position {
+    shard: usize,
+    index: usize,
+}
+
+/// The value of every shard of a [`Counter`] at some point
+pub(super) struct Snapshot(Box<[usize]>);
+
+impl Counter {
+    pub(super) fn new() -> Self {
+        Counter {
+            shards: (0..SHARDS).map(|_| Shard(AtomicUsize::new(0))).collect(),
+        }
+    }
+
+    /// Counts once in the shard of the current thread
+    pub(super) fn increment(&self) -> Position {
+        let shard = shard();
+        let index = self.shards[shard].0.fetch_add(1, Ordering::SeqCst);
+        Position { shard, index }
+    }
+
+    pub(super) fn snapshot(&self) -> Snapshot {
+        Snapshot(
+            self.shards
+                .iter()
+                .map(|shard| shard.0.load(Ordering::SeqCst))
+                .collect(),
+        )
+    }
+
+    pub(super) fn total(&self) -> usize {
+        self.snapshot().total()
+    }
+}
+
+impl Snapshot {
+    /// Whether the count at the given position was already counted
+    /// when the snapshot was taken
+    pub(super) fn includes(&self, position: Position) -> bool {
+        position.index < self.0[position.shard]
+    }
+
+    pub(super) fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+}
+
+/// The shard of the current thread
+fn shard() -> usize {
+    // not loom's: which shard a thread counts in does not matter
+    static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    thread_local! {
+        static SHARD: usize = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % SHARDS;
+    }
+
+    SHARD.with(|shard| *shard)
+}
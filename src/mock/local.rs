@@ -0,0 +1,54 @@
+use std::{
+    mem::ManuallyDrop,
+    panic::{RefUnwindSafe, UnwindSafe},
+};
+
+use crate::sync;
+
+/// A value that is not `Send`, only used by the thread that created it
+///
+/// [`get`](Local::get) is the only way to reach the value and gives
+/// nothing to any other thread. Dropping it from any other thread
+/// leaks the value instead.
+pub struct Local<T> {
+    value: ManuallyDrop<T>,
+    owner: sync::ThreadId,
+}
+
+// Safety: the value is only accessed, including being dropped, by the
+// thread that created it, which both `get` and `drop` check for
+unsafe impl<T> Send for Local<T> {}
+unsafe impl<T> Sync for Local<T> {}
+
+// like the stubs behind a lock, which ignore poisoning
+impl<T> UnwindSafe for Local<T> {}
+impl<T> RefUnwindSafe for Local<T> {}
+
+impl<T> Local<T> {
+    /// Creates a value local to the current thread
+    pub fn new(value: T) -> Self {
+        Local {
+            value: ManuallyDrop::new(value),
+            owner: sync::current_thread(),
+        }
+    }
+
+    /// The value, if called from the thread that created it
+    pub fn get(&self) -> Option<&T> {
+        if sync::current_thread() == self.owner {
+            Some(&self.value)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Drop for Local<T> {
+    fn drop(&mut self) {
+        // other threads may not drop what is not `Send`
+        if sync::current_thread() == self.owner {
+            // Safety: the value is never used again
+            unsafe { ManuallyDrop::drop(&mut self.value) }
+        }
+    }
+}
@@ -1,20 +1,59 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+};
 
 use crate::InvocationError;
 
 use super::{unchecked::Unchecked, Mock};
 
+/// Stores the mocks of every stubbed method of a mock instance
+///
+/// # Safety
+///
+/// Mocks are stored with their types erased and are keyed by the
+/// address of an `id` function. The type of `id` determines the input
+/// (I) and output (O) of its mock, so inserting and retrieving a mock
+/// with the same `id` is always done with the same types. The
+/// generated code pairs each `id` (a hidden `_faux_*` method) with its
+/// own name, which is asserted to catch the compiler ever merging two
+/// functions into the same address. The names of the erased types are
+/// asserted too, as the monomorphizations of a generic `id` share its
+/// name and may be merged when their bodies are identical.
 #[derive(Debug)]
 pub struct Store<'stub> {
     pub struct_name: &'static str,
-    stubs: HashMap<usize, Unchecked<'stub>>,
+    stubs: HashMap<usize, Unchecked<'stub>, BuildHasherDefault<AddressHasher>>,
+}
+
+/// Hashes the address of an `id` function
+///
+/// Addresses are unique and are not chosen by an attacker so they do
+/// not need the DoS resistance of the default hasher, which is a
+/// noticeable cost for mocks called in tight loops.
+#[derive(Default)]
+struct AddressHasher(u64);
+
+impl Hasher for AddressHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, _: &[u8]) {
+        unreachable!("faux bug: only addresses are hashed")
+    }
+
+    fn write_usize(&mut self, address: usize) {
+        // spreads the aligned bits of the address across the hash
+        self.0 = (address as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
 }
 
 impl<'stub> Store<'stub> {
     pub fn new(struct_name: &'static str) -> Self {
         Store {
             struct_name,
-            stubs: HashMap::new(),
+            stubs: HashMap::default(),
         }
     }
 
@@ -32,6 +71,7 @@ impl<'stub> Store<'stub> {
             mock.into()
         });
 
+        // Safety: see the docs on `Store`
         let mock = unsafe { mock.as_typed_mut() };
         assert_name(mock, fn_name);
         mock
@@ -39,7 +79,12 @@ impl<'stub> Store<'stub> {
 
     /// Returns a reference to a [`Mock`] for a given function
     ///
-    /// `None` is returned if the function was never mocked
+    /// An error is returned if the function was never mocked
+    ///
+    /// # Safety
+    ///
+    /// `id` must be paired with the same `fn_name` that was used to
+    /// stub it; see the docs on [`Store`].
     pub unsafe fn get<R, I, O>(
         &self,
         id: fn(R, I) -> O,
@@ -51,12 +96,12 @@ impl<'stub> Store<'stub> {
                 assert_name(mock, fn_name);
                 Ok(mock)
             }
-            None => Err(InvocationError {
+            None => Err(InvocationError::new(
+                self.struct_name,
                 fn_name,
-                struct_name: self.struct_name,
                 generics,
-                stub_error: super::InvocationError::NeverStubbed,
-            }),
+                super::InvocationError::NeverStubbed,
+            )),
         }
     }
 }
@@ -1,34 +1,140 @@
 use std::{
+    any::Any,
+    cell::RefCell,
     fmt::{self, Formatter},
+    future::Future,
     num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    task::{Context, Poll},
 };
 
-use crate::matcher::InvocationMatcher;
+pub use super::local::Local;
+
+use crate::{
+    matcher::{AnyInvocation, InvocationMatcher, Mismatch},
+    sync::{self, AtomicUsize, Mutex, Ordering},
+    Gate,
+};
 
 pub struct Stub<'a, I, O> {
-    matcher: Box<dyn InvocationMatcher<I> + Send>,
+    matcher: Matcher<I>,
+    remaining: Remaining,
     answer: Answer<'a, I, O>,
 }
 
+/// Checks whether a stub handles an invocation
+enum Matcher<I> {
+    /// matches every invocation without having to lock the matcher
+    Any,
+    Args(Mutex<Box<dyn InvocationMatcher<I> + Send>>),
+}
+
+/// How many more invocations a stub may handle
+struct Remaining(AtomicUsize);
+
 pub enum Answer<'a, I, O> {
-    Exhausted,
-    Once(Box<dyn FnOnce(I) -> O + Send + 'a>),
-    Many {
-        stub: Box<dyn FnMut(I) -> O + Send + 'a>,
-        times: Times,
+    Once(Single<dyn FnOnce(I) -> O + Send + 'a>),
+    Many(Shared<dyn FnMut(I) -> O + Send + 'a>),
+    /// a stub that may run concurrently so it is invoked without
+    /// locking. Asserted to be unwind safe like the stubs behind a
+    /// lock, which ignore poisoning
+    Fn(AssertUnwindSafe<Box<dyn Fn(I) -> O + Send + Sync + 'a>>),
+    OncePending(Single<dyn PendingOnce<'a, I, O> + 'a>),
+    ManyPending(Shared<dyn PendingMany<'a, I, O> + 'a>),
+    LocalOnce(Local<LocalFnOnce<'a, I, O>>),
+    LocalMany(Local<LocalFnMut<'a, I, O>>),
+    /// calls wait at the gate before returning the value of the
+    /// inner answer
+    Gated {
+        gate: Gate,
+        answer: Box<Answer<'a, I, O>>,
     },
 }
 
+/// A stub that was claimed by an invocation and is ready to be
+/// invoked without holding any lock of its mock
+pub enum Invocation<'s, 'a, I, O> {
+    Once(Box<dyn FnOnce(I) -> O + Send + 'a>),
+    Many(&'s Shared<dyn FnMut(I) -> O + Send + 'a>),
+    Fn(&'s (dyn Fn(I) -> O + Send + Sync + 'a)),
+    OncePending(Box<dyn PendingOnce<'a, I, O> + 'a>),
+    ManyPending(&'s Shared<dyn PendingMany<'a, I, O> + 'a>),
+    LocalOnce(&'s LocalFnOnce<'a, I, O>),
+    LocalMany(&'s LocalFnMut<'a, I, O>),
+    Gated(&'s Gate, Box<Invocation<'s, 'a, I, O>>),
+}
+
+/// A stub that may only be invoked once
+pub struct Single<F: ?Sized>(Mutex<Option<Box<F>>>);
+
+/// A stub that may be invoked many times
+///
+/// The stub is only locked while it runs. Invocations from different
+/// threads wait for each other.
+pub struct Shared<F: ?Sized> {
+    stub: Mutex<Box<F>>,
+    // the id of the thread running the stub, if any, to tell a stub
+    // that calls itself apart from a stub called from another thread
+    running: std::sync::atomic::AtomicUsize,
+}
+
+/// Stubs that are not `Send`, wrapped in a [`Local`] so calling them
+/// from any other thread than the one that added them panics
+pub type LocalFnOnce<'a, I, O> = RefCell<Option<Box<dyn FnOnce(I) -> O + 'a>>>;
+pub type LocalFnMut<'a, I, O> = RefCell<Box<dyn FnMut(I) -> O + 'a>>;
+
+/// Identifies a stub in panic messages
+#[derive(Clone, Copy, Debug)]
+pub struct StubId {
+    pub struct_name: &'static str,
+    pub fn_name: &'static str,
+    /// the order in which the stub was added
+    pub index: usize,
+}
+
+/// An async stub that may only be invoked once
+pub trait PendingOnce<'a, I, O>: Send {
+    fn call(self: Box<Self>, input: I, id: StubId) -> Pin<Box<dyn Future<Output = O> + Send + 'a>>;
+}
+
+/// An async stub that may be invoked multiple times
+pub trait PendingMany<'a, I, O>: Send {
+    fn call(&mut self, input: I, id: StubId) -> Pin<Box<dyn Future<Output = O> + Send + 'a>>;
+}
+
+/// Wraps a stub that returns a future so its future can be boxed
+///
+/// A closure cannot do the boxing as it would require the inputs and
+/// outputs to be `'static`
+pub struct Pending<F>(pub F);
+
+/// What an invoked stub produces
+pub enum Output<'a, O> {
+    /// the value to return
+    Ready(O),
+    /// a future that resolves to the value to return, only
+    /// available to async methods
+    Pending(Pin<Box<dyn Future<Output = O> + Send + 'a>>),
+    /// the value to return once the gate lets the call through
+    Gated(Gate, O),
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Times {
     Always,
     Times(NonZeroUsize),
 }
 
-#[derive(Debug)]
+/// Why a stub failed to handle an invocation.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum Error {
+    /// The arguments matched but the stub was already called as many
+    /// times as it was allowed to.
     Exhausted,
-    NotMatched(String),
+    /// The arguments did not match the stub's matchers.
+    NotMatched(Mismatch),
 }
 
 impl std::error::Error for Error {}
@@ -37,60 +143,336 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Error::Exhausted => f.write_str("stub was exhausted"),
-            Error::NotMatched(error) => f.write_str(error),
+            Error::NotMatched(mismatch) => fmt::Display::fmt(mismatch, f),
         }
     }
 }
 
-impl Times {
-    pub fn decrement(self) -> Option<Self> {
+impl Error {
+    /// How many arguments failed to match.
+    ///
+    /// An exhausted stub matched all its arguments. Returns `None` if
+    /// the matcher does not keep track of individual arguments.
+    pub fn mismatched_args(&self) -> Option<usize> {
         match self {
-            Times::Always => Some(self),
-            Times::Times(n) => NonZeroUsize::new(n.get() - 1).map(Times::Times),
+            Error::Exhausted => Some(0),
+            Error::NotMatched(mismatch) => mismatch.arguments().map(<[_]>::len),
         }
     }
+
+    /// A one line explanation of the error.
+    pub fn summary(&self) -> String {
+        match self {
+            Error::Exhausted => "stub was exhausted".to_owned(),
+            Error::NotMatched(mismatch) => match mismatch.arguments() {
+                Some([argument]) => format!("Argument {} did not match", argument.position()),
+                Some(arguments) => {
+                    let positions: Vec<_> =
+                        arguments.iter().map(|a| a.position().to_string()).collect();
+                    format!("Arguments {} did not match", positions.join(", "))
+                }
+                None => mismatch
+                    .message()
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_owned(),
+            },
+        }
+    }
+}
+
+impl<'a, I, O, F, Fut> PendingOnce<'a, I, O> for Pending<F>
+where
+    F: FnOnce(I) -> Fut + Send,
+    Fut: Future<Output = O> + Send + 'a,
+{
+    fn call(self: Box<Self>, input: I, id: StubId) -> Pin<Box<dyn Future<Output = O> + Send + 'a>> {
+        Box::pin(Identified {
+            future: Box::pin((self.0)(input)),
+            id,
+        })
+    }
+}
+
+impl<'a, I, O, F, Fut> PendingMany<'a, I, O> for Pending<F>
+where
+    F: FnMut(I) -> Fut + Send,
+    Fut: Future<Output = O> + Send + 'a,
+{
+    fn call(&mut self, input: I, id: StubId) -> Pin<Box<dyn Future<Output = O> + Send + 'a>> {
+        Box::pin(Identified {
+            future: Box::pin((self.0)(input)),
+            id,
+        })
+    }
 }
 
 impl<'a, I, O> Stub<'a, I, O> {
+    /// Creates a stub that handles the given number of invocations,
+    /// none if `times` is `None`
     pub fn new(
-        stub: Answer<'a, I, O>,
+        answer: Answer<'a, I, O>,
+        times: Option<Times>,
         matcher: impl InvocationMatcher<I> + Send + 'static,
     ) -> Self {
+        let matcher = if (&matcher as &dyn Any).is::<AnyInvocation>() {
+            Matcher::Any
+        } else {
+            Matcher::Args(Mutex::new(Box::new(matcher)))
+        };
+
         Stub {
-            matcher: Box::new(matcher),
-            answer: stub,
+            matcher,
+            remaining: Remaining::new(times),
+            answer,
+        }
+    }
+
+    /// Claims the stub for an invocation with the given input
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stub is local to another thread.
+    pub fn claim(&self, input: &I, id: StubId) -> Result<Invocation<'_, 'a, I, O>, Error> {
+        if let Matcher::Args(matcher) = &self.matcher {
+            // a panicking matcher should not poison the stub
+            let matcher = matcher
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            // TODO: should the error message be different if the stub is also exhausted?
+            if let Err(e) = matcher.check(input) {
+                return Err(Error::NotMatched(e));
+            }
+        }
+
+        // before taking an invocation so that calls from other
+        // threads do not use it up
+        self.answer.assert_owner(id);
+        if !self.remaining.take() {
+            return Err(Error::Exhausted);
+        }
+
+        self.answer.claim()
+    }
+}
+
+impl Remaining {
+    /// stubs without a limit are never decremented
+    const ALWAYS: usize = usize::MAX;
+
+    fn new(times: Option<Times>) -> Self {
+        Remaining(AtomicUsize::new(match times {
+            None => 0,
+            Some(Times::Always) => Self::ALWAYS,
+            Some(Times::Times(n)) => n.get(),
+        }))
+    }
+
+    /// Takes one of the remaining invocations, if any
+    fn take(&self) -> bool {
+        // a plain load keeps concurrent invocations of stubs without
+        // a limit from contending over the counter
+        if self.0.load(Ordering::Acquire) == Self::ALWAYS {
+            return true;
         }
+
+        self.0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+            .is_ok()
+    }
+}
+
+impl<'a, I, O> Answer<'a, I, O> {
+    fn claim(&self) -> Result<Invocation<'_, 'a, I, O>, Error> {
+        Ok(match self {
+            Answer::Once(stub) => Invocation::Once(stub.take()?),
+            Answer::Many(stub) => Invocation::Many(stub),
+            Answer::Fn(stub) => Invocation::Fn(&*stub.0),
+            Answer::OncePending(stub) => Invocation::OncePending(stub.take()?),
+            Answer::ManyPending(stub) => Invocation::ManyPending(stub),
+            Answer::LocalOnce(stub) => Invocation::LocalOnce(Self::local(stub)),
+            Answer::LocalMany(stub) => Invocation::LocalMany(Self::local(stub)),
+            Answer::Gated { gate, answer } => Invocation::Gated(gate, Box::new(answer.claim()?)),
+        })
     }
 
-    pub fn call(&mut self, input: I) -> Result<O, (I, Error)> {
-        // TODO: should the error message be different if the stub is also exhausted?
-        if let Err(e) = self.matcher.matches(&input) {
-            return Err((input, Error::NotMatched(e)));
+    fn assert_owner(&self, id: StubId) {
+        let owned = match self {
+            Answer::LocalOnce(stub) => stub.get().is_some(),
+            Answer::LocalMany(stub) => stub.get().is_some(),
+            Answer::Gated { answer, .. } => return answer.assert_owner(id),
+            _ => true,
+        };
+        if !owned {
+            panic!(
+                "faux: stub #{} of '{}::{}' is local to the thread that added it and cannot be called from another thread",
+                id.index, id.struct_name, id.fn_name
+            );
         }
+    }
 
-        self.answer.call(input)
+    fn local<T>(stub: &Local<T>) -> &T {
+        stub.get()
+            .expect("faux bug: a local stub was claimed by another thread")
     }
 }
 
-impl<I, O> Answer<'_, I, O> {
-    fn call(&mut self, input: I) -> Result<O, (I, Error)> {
-        // no need to replace if we can keep decrementing
-        if let Answer::Many { stub, times } = self {
-            if let Some(decremented) = times.decrement() {
-                *times = decremented;
-                return Ok(stub(input));
+impl<'a, I, O> Invocation<'_, 'a, I, O> {
+    /// Invokes the claimed stub
+    ///
+    /// Panics from the stub are reported along with the stub's
+    /// identity, including those from polling the returned future.
+    pub fn invoke(self, input: I, id: StubId) -> Output<'a, O> {
+        identify(id, || self.run(input, id))
+    }
+
+    fn run(self, input: I, id: StubId) -> Output<'a, O> {
+        match self {
+            Invocation::Once(stub) => Output::Ready(stub(input)),
+            Invocation::Many(stub) => Output::Ready((stub.lock())(input)),
+            Invocation::Fn(stub) => Output::Ready(stub(input)),
+            Invocation::OncePending(stub) => Output::Pending(stub.call(input, id)),
+            Invocation::ManyPending(stub) => Output::Pending(stub.lock().call(input, id)),
+            Invocation::LocalOnce(stub) => {
+                let stub = stub.borrow_mut().take();
+                Output::Ready(stub
+                    .expect("faux bug: a local stub was invoked more than once")(
+                    input
+                ))
+            }
+            Invocation::LocalMany(stub) => {
+                let mut stub = stub.try_borrow_mut().unwrap_or_else(|_| {
+                    panic!("faux: the stub was called again from within itself")
+                });
+                Output::Ready(stub(input))
             }
+            Invocation::Gated(gate, invocation) => match invocation.run(input, id) {
+                Output::Ready(output) => {
+                    gate.enter();
+                    Output::Gated(gate.clone(), output)
+                }
+                _ => unreachable!("faux bug: only stubbed values may be gated"),
+            },
         }
+    }
+}
+
+impl<F: ?Sized> Single<F> {
+    pub fn new(stub: Box<F>) -> Self {
+        Single(Mutex::new(Some(stub)))
+    }
+
+    fn take(&self) -> Result<Box<F>, Error> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+            .ok_or(Error::Exhausted)
+    }
+}
 
-        // otherwise replace it with an exhaust
-        match std::mem::replace(self, Answer::Exhausted) {
-            Answer::Exhausted => Err((input, Error::Exhausted)),
-            Answer::Once(stub) => Ok(stub(input)),
-            Answer::Many { mut stub, .. } => Ok(stub(input)),
+impl<F: ?Sized> Shared<F> {
+    pub fn new(stub: Box<F>) -> Self {
+        Shared {
+            stub: Mutex::new(stub),
+            running: std::sync::atomic::AtomicUsize::new(0),
         }
     }
+
+    /// Locks the stub, waiting for invocations in other threads
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stub is already running in the current thread
+    /// as it would otherwise deadlock.
+    fn lock(&self) -> impl std::ops::DerefMut<Target = Box<F>> + '_ {
+        // only the current thread sets its own id so it is seen
+        // without any synchronization
+        let current = sync::current_thread_id();
+        if self.running.load(std::sync::atomic::Ordering::Relaxed) == current {
+            panic!("faux: the stub was called again from within itself");
+        }
+
+        // a panicking stub should not poison it for later invocations
+        let stub = self
+            .stub
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.running
+            .store(current, std::sync::atomic::Ordering::Relaxed);
+        Running { stub, shared: self }
+    }
+}
+
+/// A locked stub that is no longer running once dropped
+struct Running<'s, G, F: ?Sized> {
+    stub: G,
+    shared: &'s Shared<F>,
+}
+
+impl<G: std::ops::Deref, F: ?Sized> std::ops::Deref for Running<'_, G, F> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stub
+    }
+}
+
+impl<G: std::ops::DerefMut, F: ?Sized> std::ops::DerefMut for Running<'_, G, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.stub
+    }
+}
+
+impl<G, F: ?Sized> Drop for Running<'_, G, F> {
+    fn drop(&mut self) {
+        self.shared
+            .running
+            .store(0, std::sync::atomic::Ordering::Relaxed);
+    }
 }
 
+/// Reports panics while polling the future of a stub
+struct Identified<F> {
+    future: Pin<Box<F>>,
+    id: StubId,
+}
+
+impl<F: Future> Future for Identified<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let id = self.id;
+        identify(id, || self.future.as_mut().poll(cx))
+    }
+}
+
+/// Runs a stub, adding its identity to the message of its panics
+///
+/// Panics whose payload is not a message resume untouched, as do the
+/// ones that were already identified by a stub called within this one.
+fn identify<T>(id: StubId, stub: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(stub)).unwrap_or_else(|payload| {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => Some(*message),
+            None => payload.downcast_ref::<String>().map(String::as_str),
+        };
+
+        match message {
+            Some(message) if !message.contains(IDENTITY) => {
+                panic::resume_unwind(Box::new(format!(
+                    "{}\n{}#{} of '{}::{}'",
+                    message, IDENTITY, id.index, id.struct_name, id.fn_name
+                )))
+            }
+            _ => panic::resume_unwind(payload),
+        }
+    })
+}
+
+const IDENTITY: &str = "faux: the panic came from stub ";
+
 impl<I, O> fmt::Debug for Stub<'_, I, O> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("Stub")
@@ -99,9 +481,10 @@ impl<I, O> fmt::Debug for Stub<'_, I, O> {
             .field(
                 "answer",
                 match &self.answer {
-                    Answer::Exhausted => &"Exhausted",
-                    Answer::Once(_) => &"Once",
-                    Answer::Many { .. } => &"Many",
+                    Answer::Once(_) | Answer::OncePending(_) | Answer::LocalOnce(_) => &"Once",
+                    Answer::Many(_) | Answer::ManyPending(_) | Answer::LocalMany(_) => &"Many",
+                    Answer::Fn(_) => &"Fn",
+                    Answer::Gated { .. } => &"Gated",
                 },
             )
             .finish()
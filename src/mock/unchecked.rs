@@ -14,6 +14,9 @@ use super::Mock;
 pub struct Unchecked<'stub> {
     unsafe_mock: Mock<'stub, (), ()>,
     debug_repr: String,
+    // the name of the original mock's type, used to catch mismatched
+    // types before they are transmuted
+    type_name: &'static str,
 }
 
 impl<'stub> Unchecked<'stub> {
@@ -24,7 +27,14 @@ impl<'stub> Unchecked<'stub> {
     /// This method is *extremely* unsafe. This is only safe if you
     /// know precisely what the input (I), output (O) were of the
     /// original [`Mock`] this came from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name of the requested type does not match the
+    /// original one. Type names are not guaranteed to be unique so
+    /// this is a sanity check rather than a guarantee.
     pub unsafe fn as_typed<I, O>(&self) -> &Mock<'stub, I, O> {
+        self.assert_type::<I, O>();
         // Might be safer to only transmute only the matcher and stub
         // of each mock instead of the entire object. This works
         // though, and I don't see any reason why it wouldn't but if
@@ -42,7 +52,14 @@ impl<'stub> Unchecked<'stub> {
     /// This method is *extremely* unsafe. This is only safe if you
     /// know precisely what the input (I), output (O) were of the
     /// original [`Mock`] this came from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name of the requested type does not match the
+    /// original one. Type names are not guaranteed to be unique so
+    /// this is a sanity check rather than a guarantee.
     pub unsafe fn as_typed_mut<I, O>(&mut self) -> &mut Mock<'stub, I, O> {
+        self.assert_type::<I, O>();
         // Might be safer to only transmute only the matcher and stub
         // of each mock instead of the entire object. This works
         // though, and I don't see any reason why it wouldn't but if
@@ -51,6 +68,15 @@ impl<'stub> Unchecked<'stub> {
         let mock = &mut self.unsafe_mock;
         std::mem::transmute(mock)
     }
+
+    fn assert_type<I, O>(&self) {
+        let type_name = std::any::type_name::<Mock<'stub, I, O>>();
+        assert_eq!(
+            self.type_name, type_name,
+            "faux bug: conflicting mock types: '{}' vs '{}'",
+            self.type_name, type_name
+        );
+    }
 }
 
 impl fmt::Debug for Unchecked<'_> {
@@ -65,15 +91,17 @@ impl<'stub, I, O> From<Mock<'stub, I, O>> for Unchecked<'stub> {
         // let's save its debug representation so we can print it as
         // our own
         let debug_repr = format!("{:?}", mock);
+        let type_name = std::any::type_name::<Mock<'stub, I, O>>();
         // Safety:
         // The only posible actions on the returned `Saved` are:
-        // * as_checked_mut: already marked as `unsafe`
+        // * as_typed and as_typed_mut: already marked as `unsafe`
         // * debug format: does not look into the unsafe fields
         unsafe {
             let unsafe_mock: Mock<_, _> = std::mem::transmute(mock);
             Self {
                 unsafe_mock,
                 debug_repr,
+                type_name,
             }
         }
     }
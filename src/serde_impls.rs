@@ -0,0 +1,44 @@
+//! Forwards (de)serialization of mockable structs to the real
+//! instances they wrap.
+
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Faux, MaybeFaux};
+
+impl<T: Serialize> Serialize for MaybeFaux<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MaybeFaux::Real(real) => real.serialize(serializer),
+            MaybeFaux::Faux(faux) => Err(ser::Error::custom(format!(
+                "faux: a mock instance of `{}` cannot be serialized; a stubbed `serialize` must return a real instance",
+                faux.store.struct_name
+            ))),
+        }
+    }
+}
+
+/// Deserialization always creates a real instance.
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for MaybeFaux<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(MaybeFaux::Real)
+    }
+}
+
+/// Calls the stub for `serialize` to get the instance to serialize
+/// in place of the mock.
+///
+/// Exposed so generated code can use it but purposefully not
+/// documented.
+#[doc(hidden)]
+pub fn serialize_stub<R, O, E: ser::Error>(faux: &Faux, id: fn(R, ()) -> O) -> Result<O, E> {
+    faux.call(id, "serialize", (), "").map_err(|e| {
+        if e.never_stubbed() {
+            E::custom(format!(
+                "faux: a mock instance of `{}` cannot be serialized unless `serialize` is stubbed: `faux::when!(<mock as Serialize>.serialize).then(|_| /* real instance */)`",
+                e.struct_name()
+            ))
+        } else {
+            E::custom(e)
+        }
+    })
+}
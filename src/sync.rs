@@ -0,0 +1,46 @@
+//! The synchronization primitives of the mock store
+//!
+//! With the `loom` feature and `--cfg loom`, loom's primitives are
+//! used instead of the standard library's so stub invocations
+//! participate in loom's scheduling.
+
+#[cfg(all(feature = "loom", loom))]
+pub(crate) use loom::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::ThreadId,
+};
+#[cfg(not(all(feature = "loom", loom)))]
+pub(crate) use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::ThreadId,
+};
+
+pub(crate) fn current_thread() -> ThreadId {
+    #[cfg(all(feature = "loom", loom))]
+    return loom::thread::current().id();
+    #[cfg(not(all(feature = "loom", loom)))]
+    return std::thread::current().id();
+}
+
+/// A nonzero id of the current thread that fits in an atomic
+pub(crate) fn current_thread_id() -> usize {
+    // not loom's: the ids only need to be unique
+    static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(1);
+
+    #[cfg(all(feature = "loom", loom))]
+    loom::thread_local! {
+        static ID: usize = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+    #[cfg(not(all(feature = "loom", loom)))]
+    std::thread_local! {
+        static ID: usize = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    ID.with(|id| *id)
+}
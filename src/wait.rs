@@ -0,0 +1,396 @@
+//! Tools to wait for calls to your mocks from other threads or tasks.
+
+use std::{
+    fmt::Write as _,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard, Weak},
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    matcher::{AnyInvocation, InvocationMatcher, Mismatch},
+    mock, Faux,
+};
+
+/// Waits for a stubbed method to be called.
+///
+/// Created using [`wait_for!`], which is the preferred way to use
+/// it. Blocks the current thread using [`wait`](WaitFor::wait) or
+/// returns a future using [`wait_async`](WaitFor::wait_async).
+///
+/// Without argument matchers, every call to the method counts,
+/// including those made before waiting. With argument matchers, only
+/// the matching calls made after the wait started count, as `faux`
+/// does not keep the arguments of previous calls. If the wait times
+/// out, the panic message says how many earlier calls were not
+/// checked.
+///
+/// Do *NOT* rely on the signature of `WaitFor`. While changing the
+/// methods of `WaitFor` will be considered a breaking change,
+/// changing the generics within `WaitFor` will not.
+///
+/// [`wait_for!`]: crate::wait_for!
+pub struct WaitFor<'m, R, I, O, M: InvocationMatcher<I>> {
+    id: fn(R, I) -> O,
+    name: &'static str,
+    faux: &'m Faux,
+    matcher: M,
+    count_previous: bool,
+    times: usize,
+    timeout: Duration,
+}
+
+impl<'m, R, I, O> WaitFor<'m, R, I, O, AnyInvocation> {
+    #[doc(hidden)]
+    pub fn new(id: fn(R, I) -> O, name: &'static str, faux: &'m Faux) -> Self {
+        WaitFor {
+            id,
+            name,
+            faux,
+            matcher: AnyInvocation,
+            count_previous: true,
+            times: 1,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl<'m, R, I, O, M: InvocationMatcher<I> + Send + 'static> WaitFor<'m, R, I, O, M> {
+    /// Specifies a matcher for the calls to wait for.
+    ///
+    /// See [`When::with_args`](crate::When::with_args).
+    pub fn with_args<N: InvocationMatcher<I> + Send + 'static>(
+        self,
+        matcher: N,
+    ) -> WaitFor<'m, R, I, O, N> {
+        WaitFor {
+            matcher,
+            id: self.id,
+            name: self.name,
+            faux: self.faux,
+            count_previous: false,
+            times: self.times,
+            timeout: self.timeout,
+        }
+    }
+
+    /// Sets how many calls to wait for. Defaults to 1.
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = times;
+        self
+    }
+
+    /// Sets how long to wait for. Defaults to 5 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Blocks the current thread until the method is called enough
+    /// times.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the method was never stubbed, or if it is not called
+    /// enough times before the timeout. The panic message reports the
+    /// calls that were seen.
+    pub fn wait(self) {
+        let times = self.times;
+        let timeout = self.timeout;
+        let watching = self.watch();
+        let state = watching.watch.lock();
+        let (state, _) = watching
+            .watch
+            .changed
+            .wait_timeout_while(state, timeout, |state| state.matched < times)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if state.matched < times {
+            panic!("{}", watching.report(&state, times, timeout));
+        }
+    }
+
+    /// Returns a future that resolves once the method is called
+    /// enough times.
+    ///
+    /// # Panics
+    ///
+    /// The future panics if the method was never stubbed, or if it
+    /// is not called enough times before the timeout. The panic
+    /// message reports the calls that were seen.
+    pub fn wait_async(self) -> impl Future<Output = ()> + Send + 'm
+    where
+        I: 'm,
+        O: 'm,
+    {
+        let times = self.times;
+        let timeout = self.timeout;
+        let watching = self.watch();
+        Waiting {
+            watching,
+            times,
+            timeout,
+            timer: None,
+        }
+    }
+
+    fn watch(self) -> Watching<'m, I, O> {
+        // Safety: the generated code always pairs `id` with the same
+        // name, see the docs on `mock::Store`
+        let mock = match unsafe { self.faux.store.get(self.id, self.name, "") } {
+            Ok(mock) => mock,
+            Err(e) => panic!("faux: cannot wait for calls to a method that was never stubbed: {e}"),
+        };
+        let watch = mock.watch(Box::new(self.matcher), self.count_previous);
+        Watching {
+            mock,
+            watch,
+            struct_name: self.faux.store.struct_name,
+        }
+    }
+}
+
+/// A registered watch that stops watching when dropped
+struct Watching<'m, I, O> {
+    mock: &'m mock::Mock<'static, I, O>,
+    watch: Arc<Watch>,
+    struct_name: &'static str,
+}
+
+impl<I, O> Watching<'_, I, O> {
+    fn report(&self, state: &State, times: usize, timeout: Duration) -> String {
+        let mut report = format!(
+            "faux: timed out after {:?} waiting for {} call(s) to '{}::{}' but only {} matched",
+            timeout,
+            times,
+            self.struct_name,
+            self.mock.name(),
+            state.matched,
+        );
+        if state.unchecked > 0 {
+            let _ = write!(
+                report,
+                "\n{} call(s) made before the wait started were not checked against the arguments",
+                state.unchecked
+            );
+        }
+        if !state.mismatched.is_empty() {
+            let _ = write!(
+                report,
+                "\n{} other call(s) did not match:",
+                state.mismatched.len()
+            );
+            for mismatch in &state.mismatched {
+                let _ = write!(
+                    report,
+                    "\n  - {}",
+                    mismatch.to_string().replace('\n', "\n    ")
+                );
+            }
+        }
+        report
+    }
+}
+
+impl<I, O> Drop for Watching<'_, I, O> {
+    fn drop(&mut self) {
+        self.mock.unwatch(&self.watch);
+    }
+}
+
+/// The state shared between a mock and those waiting for its calls
+pub(crate) struct Watch {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+struct State {
+    matched: usize,
+    // calls made before the wait that could not be matched
+    unchecked: usize,
+    mismatched: Vec<Mismatch>,
+    timed_out: bool,
+    wakers: Vec<Waker>,
+}
+
+impl Watch {
+    /// Creates a watch that starts with the calls made so far
+    ///
+    /// They are counted as matched if `count_previous` is set and
+    /// reported as unchecked otherwise.
+    pub(crate) fn new(previous: usize, count_previous: bool) -> Self {
+        let (matched, unchecked) = if count_previous {
+            (previous, 0)
+        } else {
+            (0, previous)
+        };
+        Watch {
+            state: Mutex::new(State {
+                matched,
+                unchecked,
+                mismatched: vec![],
+                timed_out: false,
+                wakers: vec![],
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Records a call to the watched method
+    pub(crate) fn record(&self, result: Result<(), Mismatch>) {
+        self.update(|state| match result {
+            Ok(()) => state.matched += 1,
+            Err(mismatch) => state.mismatched.push(mismatch),
+        });
+    }
+
+    fn update(&self, f: impl FnOnce(&mut State)) {
+        let wakers = {
+            let mut state = self.lock();
+            f(&mut state);
+            std::mem::take(&mut state.wakers)
+        };
+        self.changed.notify_all();
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // a panicking test should not poison the watch for other threads
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+struct Waiting<'m, I, O> {
+    watching: Watching<'m, I, O>,
+    times: usize,
+    timeout: Duration,
+    // started on the first poll and cancelled when dropped
+    timer: Option<Timer>,
+}
+
+impl<I, O> Future for Waiting<'_, I, O> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.timer.is_none() {
+            let timer = Timer::start(self.timeout, &self.watching.watch);
+            self.timer = Some(timer);
+        }
+
+        let mut state = self.watching.watch.lock();
+        if state.matched >= self.times {
+            drop(state);
+            self.timer = None;
+            return Poll::Ready(());
+        }
+        if state.timed_out {
+            panic!("{}", self.watching.report(&state, self.times, self.timeout));
+        }
+        state.wakers.push(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Times out a watch unless cancelled by being dropped first
+///
+/// There is no runtime to sleep on so a single thread, shared by
+/// every pending timer, sleeps until the earliest deadline. It exits
+/// once no timers are left.
+struct Timer {
+    id: u64,
+}
+
+struct Timers {
+    pending: Vec<(u64, Instant, Weak<Watch>)>,
+    next_id: u64,
+    running: bool,
+}
+
+static TIMERS: Mutex<Timers> = Mutex::new(Timers {
+    pending: Vec::new(),
+    next_id: 0,
+    running: false,
+});
+static TIMERS_CHANGED: Condvar = Condvar::new();
+
+impl Timer {
+    fn start(timeout: Duration, watch: &Arc<Watch>) -> Self {
+        // a timeout too large to represent never expires
+        let deadline = match Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => return Timer { id: u64::MAX },
+        };
+
+        let mut timers = Timer::lock();
+        let id = timers.next_id;
+        timers.next_id += 1;
+        timers.pending.push((id, deadline, Arc::downgrade(watch)));
+        if !timers.running {
+            timers.running = true;
+            std::thread::spawn(Timer::run);
+        }
+        drop(timers);
+        TIMERS_CHANGED.notify_all();
+        Timer { id }
+    }
+
+    fn run() {
+        let mut timers = Timer::lock();
+        loop {
+            let now = Instant::now();
+            let (expired, pending) = std::mem::take(&mut timers.pending)
+                .into_iter()
+                .partition(|(_, deadline, _)| *deadline <= now);
+            timers.pending = pending;
+
+            if !expired.is_empty() {
+                // the watches wake up their waiters so they are
+                // updated without holding onto the timers
+                drop(timers);
+                for (_, _, watch) in expired {
+                    if let Some(watch) = watch.upgrade() {
+                        watch.update(|state| state.timed_out = true);
+                    }
+                }
+                timers = Timer::lock();
+                continue;
+            }
+
+            timers = match timers
+                .pending
+                .iter()
+                .map(|(_, deadline, _)| *deadline)
+                .min()
+            {
+                Some(deadline) => {
+                    TIMERS_CHANGED
+                        .wait_timeout(timers, deadline - now)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .0
+                }
+                None => {
+                    timers.running = false;
+                    return;
+                }
+            };
+        }
+    }
+
+    fn lock() -> MutexGuard<'static, Timers> {
+        TIMERS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let mut timers = Timer::lock();
+        timers.pending.retain(|(id, _, _)| *id != self.id);
+        drop(timers);
+        TIMERS_CHANGED.notify_all();
+    }
+}
@@ -2,12 +2,12 @@
 
 mod once;
 
-use std::num::NonZeroUsize;
+use std::{cell::RefCell, future::Future, num::NonZeroUsize, panic::AssertUnwindSafe};
 
 use crate::{
     matcher::{AnyInvocation, InvocationMatcher},
     mock::{self, stub},
-    Faux,
+    Faux, Gate,
 };
 
 pub use once::Once;
@@ -73,6 +73,11 @@ impl<'m, R, I, O, M: InvocationMatcher<I> + Send + 'static> When<'m, R, I, O, M>
     /// fail unless that stub instance has its clone method already
     /// stubbed.
     ///
+    /// The value is cloned while holding the stub's lock, as `O` is
+    /// not required to be `Sync`, so calls from different threads wait
+    /// for each other. If the value is `Sync`, use
+    /// `then_fn(move |_| value.clone())` to clone it without locking.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -115,6 +120,16 @@ impl<'m, R, I, O, M: InvocationMatcher<I> + Send + 'static> When<'m, R, I, O, M>
     /// must be stubbing a method with static output. For a more lax
     /// but unsafe alternative, use [`then_unchecked`].
     ///
+    /// The closure runs without holding any of the mock's locks, so
+    /// it may call other stubbed methods of the same mock, e.g.,
+    /// through a clone of the mock. It may not call back into itself.
+    /// If the closure panics, the panic is resumed with the stub's
+    /// index and method added to its message, and the mock remains
+    /// usable. Panics whose payload is not a `&str` or a `String` are
+    /// resumed as is.
+    /// Calls from different threads wait for each other; use
+    /// [`then_fn`] for a closure that may run concurrently.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -165,6 +180,7 @@ impl<'m, R, I, O, M: InvocationMatcher<I> + Send + 'static> When<'m, R, I, O, M>
     /// }
     /// ```
     ///
+    /// [`then_fn`]: When::then_fn
     /// [`then_unchecked`]: When::then_unchecked
     pub fn then(self, stub: impl FnMut(I) -> O + 'static + Send)
     where
@@ -173,6 +189,201 @@ impl<'m, R, I, O, M: InvocationMatcher<I> + Send + 'static> When<'m, R, I, O, M>
         self.add_stub(Box::new(stub));
     }
 
+    /// Sets the implementation of the stubbed method to a closure
+    /// that may run concurrently.
+    ///
+    /// Unlike [`then`], the closure is not locked while it runs, so
+    /// calls from different threads do not wait for each other and
+    /// the closure may call back into itself. Stubs without argument
+    /// matchers or [`times`] limits are invoked without any locking.
+    /// No other stub is, which makes this the stub of choice for
+    /// mocks that are called from many threads in tight loops.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[faux::create]
+    /// pub struct Sink {}
+    ///
+    /// #[faux::methods]
+    /// impl Sink {
+    ///     pub fn send(&self, value: u32) -> bool {
+    ///       /* implementation code */
+    ///       # panic!()
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///   use std::sync::{
+    ///       atomic::{AtomicU32, Ordering},
+    ///       Arc,
+    ///   };
+    ///
+    ///   let mut mock = Sink::faux();
+    ///
+    ///   let sum = Arc::new(AtomicU32::new(0));
+    ///   let total = sum.clone();
+    ///   faux::when!(mock.send).then_fn(move |value| {
+    ///       total.fetch_add(value, Ordering::Relaxed);
+    ///       true
+    ///   });
+    ///
+    ///   std::thread::scope(|s| {
+    ///       for value in 1..=4 {
+    ///           let mock = &mock;
+    ///           s.spawn(move || assert!(mock.send(value)));
+    ///       }
+    ///   });
+    ///   assert_eq!(sum.load(Ordering::Relaxed), 10);
+    /// }
+    /// ```
+    ///
+    /// [`then`]: When::then
+    /// [`times`]: When::times
+    pub fn then_fn(self, stub: impl Fn(I) -> O + 'static + Send + Sync)
+    where
+        O: 'static,
+    {
+        self.add_answer(stub::Answer::Fn(AssertUnwindSafe(Box::new(stub))));
+    }
+
+    /// Sets the implementation of the stubbed method to a closure
+    /// that is not `Send`.
+    ///
+    /// The closure may capture state that is not `Send`, e.g.,
+    /// `Rc<RefCell<_>>`, for mocks that are only used by a single
+    /// thread. The stub is local to the thread that adds it: calling
+    /// it from any other thread panics, and dropping the mock from any
+    /// other thread leaks the closure rather than dropping it there.
+    /// Like [`then`], the closure may not call back into itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::{cell::RefCell, rc::Rc};
+    ///
+    /// #[faux::create]
+    /// pub struct Foo {}
+    ///
+    /// #[faux::methods]
+    /// impl Foo {
+    ///     pub fn single_arg(&self, a: u8) -> usize {
+    ///       /* implementation code */
+    ///       # panic!()
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///   let mut mock = Foo::faux();
+    ///
+    ///   let seen = Rc::new(RefCell::new(vec![]));
+    ///   let recorder = seen.clone();
+    ///   faux::when!(mock.single_arg).then_local(move |a| {
+    ///       recorder.borrow_mut().push(a);
+    ///       recorder.borrow().len()
+    ///   });
+    ///
+    ///   assert_eq!(mock.single_arg(3), 1);
+    ///   assert_eq!(mock.single_arg(5), 2);
+    ///   assert_eq!(*seen.borrow(), vec![3, 5]);
+    /// }
+    /// ```
+    ///
+    /// [`then`]: When::then
+    pub fn then_local(self, stub: impl FnMut(I) -> O + 'static)
+    where
+        O: 'static,
+    {
+        self.add_answer(stub::Answer::LocalMany(stub::Local::new(RefCell::new(
+            Box::new(stub),
+        ))));
+    }
+
+    /// Sets the return value of the stubbed method to a value that is
+    /// not `Send`.
+    ///
+    /// The returned value will be cloned on each invocation of the
+    /// stub. See [`then_local`] for how the stub is local to the
+    /// thread that adds it.
+    ///
+    /// [`then_local`]: When::then_local
+    pub fn then_local_return(self, value: O)
+    where
+        O: Clone + 'static,
+    {
+        self.then_local(move |_: I| value.clone());
+    }
+
+    /// Sets the implementation of the stubbed async method to a
+    /// closure that returns a future.
+    ///
+    /// The future is awaited inside the mocked method so the stub may
+    /// wait on other futures, e.g., a channel or a timer, before
+    /// returning. Only async methods (`async fn`) may be stubbed with
+    /// this method.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[faux::create]
+    /// pub struct Foo {}
+    ///
+    /// #[faux::methods]
+    /// impl Foo {
+    ///     pub async fn fetch(&self, id: u32) -> String {
+    ///       /* implementation code */
+    ///       # panic!()
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///   let mut mock = Foo::faux();
+    ///
+    ///   let (sender, receiver) = futures::channel::oneshot::channel();
+    ///   faux::when!(mock.fetch).once().then_async(|id| async move {
+    ///       let name: String = receiver.await.unwrap();
+    ///       format!("{name}-{id}")
+    ///   });
+    ///
+    ///   sender.send("user".to_owned()).unwrap();
+    ///   let fetched = futures::executor::block_on(mock.fetch(3));
+    ///   assert_eq!(fetched, "user-3");
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// The mocked method panics when called if it is not async.
+    pub fn then_async<F>(self, stub: impl FnMut(I) -> F + 'static + Send)
+    where
+        F: Future<Output = O> + Send + 'static,
+    {
+        self.add_answer(stub::Answer::ManyPending(stub::Shared::new(Box::new(
+            stub::Pending(stub),
+        ))));
+    }
+
+    /// Sets the return value of the stubbed method, returned only
+    /// once the [`Gate`] lets the call through.
+    ///
+    /// Calls to sync methods park the calling thread at the gate
+    /// while calls to async methods return a pending future. See
+    /// [`Gate`] for an example.
+    ///
+    /// The returned value will be cloned on each invocation of the
+    /// stub.
+    pub fn then_gated(self, gate: &Gate, value: O)
+    where
+        O: Send + Clone + 'static,
+    {
+        self.add_answer(stub::Answer::Gated {
+            gate: gate.clone(),
+            answer: Box::new(stub::Answer::Many(stub::Shared::new(Box::new(
+                move |_: I| value.clone(),
+            )))),
+        });
+    }
+
     /// Analog of [`then_return`] that allows stubbing non-static
     /// return values.
     ///
@@ -486,13 +697,14 @@ impl<'m, R, I, O, M: InvocationMatcher<I> + Send + 'static> When<'m, R, I, O, M>
     }
 
     fn add_stub(self, stub: Box<dyn FnMut(I) -> O + Send + 'static>) {
-        let answer = match self.times {
-            None => stub::Answer::Exhausted,
-            Some(times) => stub::Answer::Many { times, stub },
-        };
+        self.add_answer(stub::Answer::Many(stub::Shared::new(stub)));
+    }
 
-        self.store
-            .get_mut(self.id, self.name)
-            .add_stub(Stub::new(answer, self.matcher));
+    fn add_answer(self, answer: stub::Answer<'static, I, O>) {
+        self.store.get_mut(self.id, self.name).add_stub(Stub::new(
+            answer,
+            self.times,
+            self.matcher,
+        ));
     }
 }
@@ -1,3 +1,5 @@
+use std::{cell::RefCell, future::Future, num::NonZeroUsize};
+
 use crate::{
     matcher::InvocationMatcher,
     mock::{self, stub, Stub},
@@ -105,6 +107,94 @@ impl<'m, R, I, O, M: InvocationMatcher<I> + Send + 'static> Once<'m, R, I, O, M>
         self.add_stub(Box::new(stub))
     }
 
+    /// Analog of [When.then_local] where the stub may consume
+    /// captured variables.
+    ///
+    /// [When.then_local]: struct.When.html#method.then_local
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::rc::Rc;
+    ///
+    /// #[faux::create]
+    /// pub struct Foo {}
+    ///
+    /// #[faux::methods]
+    /// impl Foo {
+    ///     pub fn single_arg(&self, a: u8) -> usize {
+    ///       /* implementation code */
+    ///       # panic!()
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///   let mut mock = Foo::faux();
+    ///
+    ///   let shared = Rc::new(25);
+    ///   let moved = shared.clone();
+    ///   faux::when!(mock.single_arg).once().then_local(move |_| Rc::strong_count(&moved));
+    ///   assert_eq!(mock.single_arg(8), 2);
+    ///   assert_eq!(Rc::strong_count(&shared), 1);
+    /// }
+    /// ```
+    pub fn then_local(self, stub: impl FnOnce(I) -> O + 'static)
+    where
+        O: 'static,
+    {
+        self.add_answer(stub::Answer::LocalOnce(stub::Local::new(RefCell::new(
+            Some(Box::new(stub)),
+        ))))
+    }
+
+    /// Analog of [When.then_local_return] where the value does not
+    /// need to be cloneable.
+    ///
+    /// [When.then_local_return]: struct.When.html#method.then_local_return
+    pub fn then_local_return(self, value: O)
+    where
+        O: 'static,
+    {
+        self.then_local(move |_| value)
+    }
+
+    /// Analog of [When.then_async] where the stub may consume
+    /// captured variables.
+    ///
+    /// [When.then_async]: struct.When.html#method.then_async
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #[faux::create]
+    /// pub struct Foo {}
+    ///
+    /// #[faux::methods]
+    /// impl Foo {
+    ///     pub async fn fetch(&self) -> Vec<u8> {
+    ///       /* implementation code */
+    ///       # panic!()
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///   let mut mock = Foo::faux();
+    ///
+    ///   let vec = vec![25];
+    ///   // moves vec to the future
+    ///   faux::when!(mock.fetch).once().then_async(|_| async move { vec });
+    ///   assert_eq!(futures::executor::block_on(mock.fetch()), vec![25]);
+    /// }
+    /// ```
+    pub fn then_async<F>(self, stub: impl FnOnce(I) -> F + 'static + Send)
+    where
+        F: Future<Output = O> + Send + 'static,
+    {
+        self.add_answer(stub::Answer::OncePending(stub::Single::new(Box::new(
+            stub::Pending(stub),
+        ))))
+    }
+
     /// Analog of [When.then_unchecked_return] where the value does
     /// not need to be cloneable.
     ///
@@ -186,8 +276,14 @@ impl<'m, R, I, O, M: InvocationMatcher<I> + Send + 'static> Once<'m, R, I, O, M>
     }
 
     fn add_stub(self, stub: Box<dyn FnOnce(I) -> O + Send + 'static>) {
-        self.store
-            .get_mut(self.id, self.name)
-            .add_stub(Stub::new(stub::Answer::Once(stub), self.matcher));
+        self.add_answer(stub::Answer::Once(stub::Single::new(stub)));
+    }
+
+    fn add_answer(self, answer: stub::Answer<'static, I, O>) {
+        self.store.get_mut(self.id, self.name).add_stub(Stub::new(
+            answer,
+            Some(stub::Times::Times(NonZeroUsize::new(1).unwrap())),
+            self.matcher,
+        ));
     }
 }
@@ -0,0 +1,131 @@
+// native `async fn` and `impl Trait` in traits need Rust 1.75, newer
+// than the MSRV
+#[rustversion::since(1.75)]
+#[allow(async_fn_in_trait, clippy::manual_async_fn)]
+mod native {
+    use std::{
+        future::Future,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    pub trait Fetcher {
+        async fn fetch(&self, id: u32) -> String;
+
+        fn load(&self, id: u32) -> impl Future<Output = u32> + Send;
+    }
+
+    #[faux::create]
+    pub struct Service {
+        offset: u32,
+    }
+
+    #[faux::methods]
+    impl Service {
+        pub fn new(offset: u32) -> Self {
+            Service { offset }
+        }
+
+        pub fn count(&self) -> impl Future<Output = u32> + Send + '_ {
+            async move { self.offset }
+        }
+
+        pub fn start(&self, started: Arc<AtomicBool>) -> impl Future<Output = u32> + Send {
+            started.store(true, Ordering::SeqCst);
+            let offset = self.offset;
+            async move { offset }
+        }
+    }
+
+    #[faux::methods]
+    impl Fetcher for Service {
+        async fn fetch(&self, id: u32) -> String {
+            format!("real {}", id + self.offset)
+        }
+
+        fn load(&self, id: u32) -> impl Future<Output = u32> + Send {
+            let offset = self.offset;
+            async move { id + offset }
+        }
+    }
+
+    fn assert_send<T: Send>(value: T) -> T {
+        value
+    }
+
+    #[test]
+    fn real_instance() {
+        let service = Service::new(10);
+        futures::executor::block_on(async {
+            assert_eq!(service.fetch(1).await, "real 11");
+            assert_eq!(service.load(2).await, 12);
+            assert_eq!(service.count().await, 10);
+        });
+    }
+
+    #[test]
+    fn real_future_is_created_when_called() {
+        let service = Service::new(3);
+        let started = Arc::new(AtomicBool::new(false));
+
+        let start = service.start(started.clone());
+        // the synchronous part ran before the future was polled
+        assert!(started.load(Ordering::SeqCst));
+        assert_eq!(futures::executor::block_on(start), 3);
+    }
+
+    #[test]
+    fn native_async_fn() {
+        let mut service = Service::faux();
+        faux::when!(service.fetch(3)).then(|id| format!("mock {id}"));
+
+        let fetched = futures::executor::block_on(service.fetch(3));
+        assert_eq!(fetched, "mock 3");
+    }
+
+    #[test]
+    fn returns_impl_future() {
+        let mut service = Service::faux();
+        faux::when!(service.load).then(|id| id * 2);
+        faux::when!(service.count).then_return(4);
+
+        futures::executor::block_on(async {
+            assert_eq!(service.load(5).await, 10);
+            assert_eq!(service.count().await, 4);
+        });
+    }
+
+    #[test]
+    fn returns_impl_future_stubbed_async() {
+        let mut service = Service::faux();
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        faux::when!(<service as Fetcher>.load)
+            .once()
+            .then_async(|_| async { receiver.await.unwrap() });
+
+        let loaded = futures::executor::block_on(async {
+            let load = service.load(1);
+            sender.send(8).unwrap();
+            load.await
+        });
+        assert_eq!(loaded, 8);
+    }
+
+    #[test]
+    fn future_is_send() {
+        let mut service = Service::faux();
+        faux::when!(service.load).then_async(|id| async move { id + 1 });
+
+        let loaded = thread::scope(|scope| {
+            let load = assert_send(service.load(1));
+            scope
+                .spawn(move || futures::executor::block_on(load))
+                .join()
+                .unwrap()
+        });
+        assert_eq!(loaded, 2);
+    }
+}
@@ -39,3 +39,71 @@ fn mocked() {
     let fetched = futures::executor::block_on(foo.fetch());
     assert_eq!(fetched, 10);
 }
+
+#[test]
+fn mocked_async() {
+    let mut foo = Foo::faux();
+    faux::when!(foo.fetch).then_async(|_| async { 10 });
+    let fetched = futures::executor::block_on(foo.fetch());
+    assert_eq!(fetched, 10);
+}
+
+#[test]
+fn async_stub_awaits_signal() {
+    let mut foo = Foo::faux();
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    faux::when!(foo.fetch)
+        .once()
+        .then_async(|_| async { receiver.await.unwrap() });
+
+    let fetched = futures::executor::block_on(async {
+        let fetch = foo.fetch();
+        sender.send(7).unwrap();
+        fetch.await
+    });
+    assert_eq!(fetched, 7);
+}
+
+#[test]
+fn async_stub_times() {
+    let mut foo = Foo::faux();
+    faux::when!(foo.fetch).then_return(1);
+    faux::when!(foo.fetch).times(2).then_async(|_| async { 2 });
+
+    futures::executor::block_on(async {
+        assert_eq!(foo.fetch().await, 2);
+        assert_eq!(foo.fetch().await, 2);
+        assert_eq!(foo.fetch().await, 1);
+    });
+}
+
+#[test]
+fn mocked_future_is_send() {
+    fn assert_send<T: Send>(_: &T) {}
+
+    let mut foo = Foo::faux();
+    faux::when!(foo.fetch).then_async(|_| async { 10 });
+    let fetch = foo.fetch();
+    assert_send(&fetch);
+    assert_eq!(futures::executor::block_on(fetch), 10);
+}
+
+#[faux::create]
+pub struct Bar {}
+
+#[faux::methods]
+impl Bar {
+    pub fn get(&self) -> i32 {
+        3
+    }
+}
+
+#[test]
+#[should_panic(
+    expected = "'Bar::get' was stubbed using `then_async` but it is not an async method"
+)]
+fn async_stub_on_sync_method() {
+    let mut bar = Bar::faux();
+    faux::when!(bar.get).then_async(|_| async { 10 });
+    bar.get();
+}
@@ -0,0 +1,173 @@
+use std::{future::Future, pin::Pin, thread};
+
+use futures::future::{BoxFuture, LocalBoxFuture};
+
+#[async_trait::async_trait]
+pub trait Runner {
+    async fn run(&self, arg: u32) -> String;
+}
+
+#[async_trait::async_trait]
+pub trait Stopper {
+    async fn stop(&self, reason: &str) -> usize;
+}
+
+#[faux::create]
+pub struct Service {
+    name: String,
+}
+
+// `async-trait` expands first so `faux` sees the boxed future
+#[async_trait::async_trait]
+#[faux::methods]
+impl Runner for Service {
+    async fn run(&self, arg: u32) -> String {
+        format!("{} ran {}", self.name, arg)
+    }
+}
+
+// `faux` expands first so `async-trait` sees the `async fn`
+#[faux::methods]
+#[async_trait::async_trait]
+impl Stopper for Service {
+    async fn stop(&self, reason: &str) -> usize {
+        reason.len()
+    }
+}
+
+#[faux::methods]
+impl Service {
+    pub fn new(name: &str) -> Self {
+        Service {
+            name: name.to_owned(),
+        }
+    }
+
+    pub fn boxed<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> Pin<Box<dyn Future<Output = String> + Send + 'a>> {
+        Box::pin(async move { format!("{prefix}{}", self.name) })
+    }
+
+    pub fn box_future(&self, id: u32) -> BoxFuture<'_, u32> {
+        Box::pin(async move { id })
+    }
+
+    pub fn local_box_future(&self) -> LocalBoxFuture<'static, u32> {
+        Box::pin(async { 7 })
+    }
+}
+
+#[faux::create]
+pub struct Worker {
+    id: u32,
+}
+
+#[faux::methods]
+impl Worker {
+    pub fn new(id: u32) -> Self {
+        Worker { id }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    // the real future is wrapped to return the mockable struct
+    pub fn restart(&self) -> BoxFuture<'static, Self> {
+        let id = self.id + 1;
+        Box::pin(async move { Worker { id } })
+    }
+}
+
+#[test]
+fn real_instance() {
+    let service = Service::new("real");
+    futures::executor::block_on(async {
+        assert_eq!(service.run(1).await, "real ran 1");
+        assert_eq!(service.stop("done").await, 4);
+        assert_eq!(service.boxed("a ").await, "a real");
+        assert_eq!(service.box_future(2).await, 2);
+        assert_eq!(service.local_box_future().await, 7);
+    });
+}
+
+#[test]
+fn async_trait_expanded_first() {
+    let mut service = Service::faux();
+    faux::when!(service.run(3)).then(|arg| format!("mock ran {arg}"));
+
+    let ran = futures::executor::block_on(service.run(3));
+    assert_eq!(ran, "mock ran 3");
+}
+
+#[test]
+fn async_trait_expanded_last() {
+    let mut service = Service::faux();
+    faux::when!(service.stop).then(|reason| reason.len() * 2);
+
+    let stopped = futures::executor::block_on(service.stop("done"));
+    assert_eq!(stopped, 8);
+}
+
+#[test]
+fn boxed_futures() {
+    let mut service = Service::faux();
+    faux::when!(service.boxed("b")).then(|prefix| format!("{prefix} mock"));
+    faux::when!(service.box_future).then(|id| id + 1);
+    faux::when!(service.local_box_future).then_return(8);
+
+    futures::executor::block_on(async {
+        assert_eq!(service.boxed("b").await, "b mock");
+        assert_eq!(service.box_future(1).await, 2);
+        assert_eq!(service.local_box_future().await, 8);
+    });
+}
+
+#[test]
+fn boxed_future_stubbed_async() {
+    let mut service = Service::faux();
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    faux::when!(<service as Runner>.run)
+        .once()
+        .then_async(|_| async { receiver.await.unwrap() });
+
+    let ran = futures::executor::block_on(async {
+        let run = service.run(1);
+        sender.send("later".to_owned()).unwrap();
+        run.await
+    });
+    assert_eq!(ran, "later");
+}
+
+#[test]
+fn boxed_future_is_send() {
+    let mut service = Service::faux();
+    faux::when!(service.run).then(|arg| arg.to_string());
+
+    let ran = thread::scope(|scope| {
+        let run = service.run(5);
+        scope
+            .spawn(move || futures::executor::block_on(run))
+            .join()
+            .unwrap()
+    });
+    assert_eq!(ran, "5");
+}
+
+#[test]
+fn boxed_future_returning_self() {
+    let worker = Worker::new(1);
+    let restarted = futures::executor::block_on(worker.restart());
+    assert_eq!(restarted.id(), 2);
+
+    let mut worker = Worker::faux();
+    faux::when!(worker.restart).then(|_| {
+        let mut restarted = Worker::faux();
+        faux::when!(restarted.id).then_return(5);
+        restarted
+    });
+    let restarted = futures::executor::block_on(worker.restart());
+    assert_eq!(restarted.id(), 5);
+}
@@ -0,0 +1,205 @@
+#![allow(clippy::new_without_default, clippy::manual_async_fn)]
+
+use std::future::Future;
+
+use futures::{task::Poll, FutureExt};
+
+#[faux::create]
+pub struct Service {}
+
+#[faux::methods]
+impl Service {
+    pub fn new() -> Self {
+        Service {}
+    }
+
+    pub fn get(&self, id: u32) -> u32 {
+        id
+    }
+
+    pub async fn fetch(&self) -> u32 {
+        1
+    }
+
+    pub fn load(&self) -> impl Future<Output = u32> + Send {
+        async { 2 }
+    }
+}
+
+#[test]
+fn sync_calls_complete() {
+    let mut service = Service::faux();
+    faux::when!(service.get(1)).then_return(5);
+
+    service.get(1);
+    service.get(1);
+
+    let calls = faux::calls!(service.get);
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls.completed(), 2);
+}
+
+#[test]
+fn unanswered_calls_are_not_included() {
+    let mut service = Service::faux();
+    faux::when!(service.get(1)).then_return(5);
+
+    let unmatched = std::panic::catch_unwind(|| service.get(2));
+    assert!(unmatched.is_err());
+
+    assert!(faux::calls!(service.get).is_empty());
+}
+
+#[test]
+fn never_stubbed() {
+    let service = Service::faux();
+    assert!(faux::calls!(service.fetch).is_empty());
+}
+
+#[test]
+fn async_completed() {
+    let mut service = Service::faux();
+    faux::when!(service.fetch).then_return(3);
+
+    futures::executor::block_on(service.fetch());
+
+    let calls = faux::calls!(service.fetch);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls.completed(), 1);
+}
+
+#[test]
+fn async_cancelled() {
+    let mut service = Service::faux();
+    faux::when!(service.fetch).then_async(|_| futures::future::pending());
+
+    assert!(service.fetch().now_or_never().is_none());
+
+    let calls = faux::calls!(service.fetch);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls.cancelled(), 1);
+}
+
+#[test]
+fn sync_panicked() {
+    let mut service = Service::faux();
+    faux::when!(service.get(1)).then(|_| panic!("boom"));
+
+    let panicked = std::panic::catch_unwind(|| service.get(1));
+    assert!(panicked.is_err());
+
+    let calls = faux::calls!(service.get);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls.panicked(), 1);
+    assert_eq!(calls.completed(), 0);
+}
+
+#[test]
+fn async_panicked_when_polled() {
+    let mut service = Service::faux();
+    faux::when!(service.load).then_async(|_| async { panic!("boom") });
+
+    let load = service.load();
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+        futures::executor::block_on(load)
+    }));
+    assert!(panicked.is_err());
+
+    let calls = faux::calls!(service.load);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls.panicked(), 1);
+    assert_eq!(calls.cancelled(), 0);
+    assert_eq!(calls.pending(), 0);
+}
+
+#[test]
+fn async_pending_until_released() {
+    let gate = faux::Gate::new();
+    let mut service = Service::faux();
+    faux::when!(service.fetch).then_gated(&gate, 3);
+
+    futures::executor::block_on(async {
+        let fetch = service.fetch();
+        futures::pin_mut!(fetch);
+
+        assert_eq!(futures::poll!(fetch.as_mut()), Poll::Pending);
+        assert_eq!(faux::calls!(service.fetch).pending(), 1);
+
+        gate.release();
+        assert_eq!(fetch.await, 3);
+    });
+
+    assert_eq!(faux::calls!(service.fetch).completed(), 1);
+}
+
+#[test]
+fn async_fn_never_polled_is_not_included() {
+    let mut service = Service::faux();
+    faux::when!(service.fetch).then_return(3);
+
+    drop(service.fetch());
+
+    assert!(faux::calls!(service.fetch).is_empty());
+}
+
+#[test]
+fn returned_future_never_polled() {
+    let mut service = Service::faux();
+    faux::when!(service.load).then_return(3);
+
+    let load = service.load();
+    assert_eq!(faux::calls!(service.load).not_polled(), 1);
+    drop(load);
+
+    let calls = faux::calls!(service.load);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls.not_polled(), 1);
+    assert_eq!(calls.cancelled(), 0);
+}
+
+#[test]
+fn mixed_statuses() {
+    let mut service = Service::faux();
+    faux::when!(service.load).then_return(3);
+    faux::when!(service.load)
+        .once()
+        .then_async(|_| futures::future::pending());
+
+    assert!(service.load().now_or_never().is_none());
+    assert_eq!(futures::executor::block_on(service.load()), 3);
+    let _not_polled = service.load();
+
+    let calls = faux::calls!(service.load);
+    assert_eq!(calls.len(), 3);
+    assert_eq!(calls.cancelled(), 1);
+    assert_eq!(calls.completed(), 1);
+    assert_eq!(calls.not_polled(), 1);
+    assert_eq!(calls.pending(), 0);
+}
+
+#[test]
+fn counts_calls_from_many_threads() {
+    let mut service = Service::faux();
+    faux::when!(service.fetch).then_return(3);
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..50 {
+                    futures::executor::block_on(service.fetch());
+                }
+            });
+        }
+    });
+
+    let calls = faux::calls!(service.fetch);
+    assert_eq!(calls.len(), 200);
+    assert_eq!(calls.completed(), 200);
+}
+
+#[test]
+#[should_panic(expected = "not allowed to get the calls of a real instance!")]
+fn real_instance() {
+    let service = Service::new();
+    faux::calls!(service.get);
+}
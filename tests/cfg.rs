@@ -0,0 +1,51 @@
+// integration tests are compiled with `cfg(test)`
+
+#[faux::create(cfg = "test")]
+pub struct Mocked {
+    a: i32,
+}
+
+#[faux::methods(cfg = "test")]
+impl Mocked {
+    pub fn new(a: i32) -> Self {
+        Mocked { a }
+    }
+
+    pub fn get(&self) -> i32 {
+        self.a
+    }
+}
+
+#[faux::create(cfg = "not(test)")]
+#[derive(Debug, PartialEq)]
+pub struct Untouched {
+    pub a: i32,
+}
+
+#[faux::methods(cfg = "not(test)")]
+impl Untouched {
+    // a no-op when the impl is not transformed
+    #[faux::real]
+    pub fn get(&self) -> i32 {
+        self.a
+    }
+}
+
+#[test]
+fn mockable_when_enabled() {
+    let real = Mocked::new(3);
+    assert_eq!(real.get(), 3);
+
+    let mut mock = Mocked::faux();
+    faux::when!(mock.get).then_return(5);
+    assert_eq!(mock.get(), 5);
+}
+
+#[test]
+fn original_when_disabled() {
+    // the fields are still accessible as the struct was not transformed
+    let untouched = Untouched { a: 4 };
+    assert_eq!(untouched.a, 4);
+    assert_eq!(untouched.get(), 4);
+    assert_eq!(untouched, Untouched { a: 4 });
+}
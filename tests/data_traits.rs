@@ -0,0 +1,77 @@
+// mocks hash by identity so their interior mutability does not affect their hash
+#![allow(clippy::redundant_clone, clippy::mutable_key_type)]
+
+use std::collections::{BTreeSet, HashMap};
+
+#[faux::create]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Foo {
+    a: i32,
+}
+
+#[faux::methods]
+impl Foo {
+    pub fn new(a: i32) -> Self {
+        Foo { a }
+    }
+
+    pub fn get(&self) -> i32 {
+        self.a
+    }
+}
+
+#[test]
+fn real_instances_compare_by_value() {
+    assert_eq!(Foo::new(3), Foo::new(3));
+    assert_ne!(Foo::new(3), Foo::new(4));
+    assert!(Foo::new(3) < Foo::new(4));
+}
+
+#[test]
+fn mocks_compare_by_identity() {
+    let mock = Foo::faux();
+    let cloned = mock.clone();
+    let other = Foo::faux();
+
+    assert_eq!(mock, mock);
+    assert_eq!(mock, cloned);
+    assert_ne!(mock, other);
+    assert_ne!(mock.cmp(&other), std::cmp::Ordering::Equal);
+}
+
+#[test]
+fn real_and_mock_are_never_equal() {
+    let real = Foo::new(3);
+    let mock = Foo::faux();
+
+    assert_ne!(real, mock);
+    assert!(real < mock);
+}
+
+#[test]
+fn hash_map_keys() {
+    let mut mock = Foo::faux();
+    faux::when!(mock.get).then_return(7);
+    let other = Foo::faux();
+
+    let mut map = HashMap::new();
+    map.insert(Foo::new(3), "real");
+    map.insert(mock.clone(), "mock");
+    map.insert(other, "other");
+
+    assert_eq!(map.len(), 3);
+    assert_eq!(map[&Foo::new(3)], "real");
+    assert_eq!(map[&mock], "mock");
+}
+
+#[test]
+fn ordered_sets() {
+    let mock = Foo::faux();
+    let set: BTreeSet<_> = vec![mock.clone(), Foo::new(2), Foo::new(1), mock]
+        .into_iter()
+        .collect();
+
+    let reals: Vec<_> = set.iter().take(2).map(Foo::get).collect();
+    assert_eq!(reals, vec![1, 2]);
+    assert_eq!(set.len(), 3);
+}
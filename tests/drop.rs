@@ -0,0 +1,93 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+#[faux::create]
+#[derive(Clone)]
+pub struct Connection {
+    closed: Arc<AtomicUsize>,
+}
+
+#[faux::methods]
+impl Connection {
+    pub fn new(closed: Arc<AtomicUsize>) -> Self {
+        Connection { closed }
+    }
+
+    pub fn send(&self, message: &str) -> usize {
+        message.len()
+    }
+
+    pub fn into_inner(self) -> Arc<AtomicUsize> {
+        self.closed.clone()
+    }
+}
+
+#[faux::methods]
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.closed.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn send_and_close(connection: Connection) -> usize {
+    connection.send("bye")
+}
+
+#[test]
+fn real_instance() {
+    let closed = Arc::new(AtomicUsize::new(0));
+    let connection = Connection::new(closed.clone());
+    assert_eq!(send_and_close(connection), 3);
+    assert_eq!(closed.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn real_by_value_method() {
+    let closed = Arc::new(AtomicUsize::new(0));
+    let connection = Connection::new(closed.clone());
+    let inner = connection.into_inner();
+    assert_eq!(inner.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn mock_dropped() {
+    let mut connection = Connection::faux();
+    faux::when!(connection.send).then_return(10);
+
+    let handle = faux::drop_handle!(connection);
+    assert!(!handle.is_dropped());
+
+    assert_eq!(send_and_close(connection), 10);
+    assert!(handle.is_dropped());
+    faux::verify_dropped(&handle);
+}
+
+#[test]
+fn mock_clones_must_all_be_dropped() {
+    let connection = Connection::faux();
+    let clone = connection.clone();
+    let handle = faux::drop_handle!(connection);
+
+    drop(connection);
+    assert!(!handle.is_dropped());
+
+    drop(clone);
+    faux::verify_dropped(&handle);
+}
+
+#[test]
+#[should_panic(expected = "expected mock of 'Connection' to be dropped")]
+fn mock_not_dropped() {
+    let connection = Connection::faux();
+    let handle = faux::drop_handle!(connection);
+    faux::verify_dropped(&handle);
+}
+
+#[test]
+#[should_panic(expected = "can only be used on mock instances")]
+fn real_handle() {
+    let connection = Connection::new(Arc::new(AtomicUsize::new(0)));
+    faux::drop_handle!(connection);
+}
@@ -0,0 +1,92 @@
+use std::rc::Rc;
+
+#[faux::create]
+pub struct Counter {
+    count: u32,
+}
+
+#[faux::methods]
+impl Counter {
+    pub fn new(count: u32) -> Self {
+        Counter { count }
+    }
+
+    pub fn get(&self) -> u32 {
+        self.count
+    }
+}
+
+// not tagged by #[faux::methods]
+impl Counter {
+    pub fn count(&self) -> Option<u32> {
+        self.faux_real().map(|real| real.count)
+    }
+
+    pub fn reset(&mut self) {
+        self.faux_real_mut()
+            .expect("cannot reset a mock counter")
+            .count = 0;
+    }
+}
+
+mod sibling {
+    use super::Counter;
+
+    pub fn double(counter: &Counter) -> u32 {
+        counter.faux_real().map_or(0, |real| real.count * 2)
+    }
+}
+
+#[faux::create(self_type = "Rc")]
+pub struct Shared {
+    name: String,
+}
+
+#[faux::methods(self_type = "Rc")]
+impl Shared {
+    pub fn new(name: String) -> Rc<Self> {
+        Rc::new(Shared { name })
+    }
+}
+
+impl Shared {
+    pub fn name(&self) -> Option<&str> {
+        self.faux_real().map(|real| real.name.as_str())
+    }
+}
+
+#[test]
+fn real_instance() {
+    let mut counter = Counter::new(5);
+    assert_eq!(counter.count(), Some(5));
+    assert_eq!(sibling::double(&counter), 10);
+
+    counter.reset();
+    assert_eq!(counter.get(), 0);
+}
+
+#[test]
+fn mock_instance() {
+    let mut counter = Counter::faux();
+    faux::when!(counter.get).then_return(7);
+
+    assert_eq!(counter.count(), None);
+    assert_eq!(sibling::double(&counter), 0);
+    assert_eq!(counter.get(), 7);
+}
+
+#[test]
+#[should_panic(expected = "cannot reset a mock counter")]
+fn mock_mut() {
+    let mut counter = Counter::faux();
+    counter.reset();
+}
+
+#[test]
+fn self_type() {
+    let shared = Shared::new("shared".to_owned());
+    assert_eq!(shared.name(), Some("shared"));
+
+    let mock = Shared::faux();
+    assert_eq!(mock.name(), None);
+}
@@ -0,0 +1,107 @@
+#![forbid(unsafe_code)]
+#![allow(clippy::boxed_local)]
+
+use std::{rc::Rc, sync::Arc};
+
+pub trait Greet {
+    fn greet(&self, name: &str) -> String;
+}
+
+#[faux::create]
+pub struct Foo {
+    a: u32,
+}
+
+#[faux::methods]
+impl Foo {
+    pub fn new(a: u32) -> Self {
+        Foo { a }
+    }
+
+    pub fn get(&self) -> u32 {
+        self.a
+    }
+
+    pub fn set(&mut self, a: u32) {
+        self.a = a;
+    }
+
+    pub fn plus(self: Rc<Self>, b: u32) -> u32 {
+        self.a + b
+    }
+
+    pub fn boxed(self: Box<Self>) -> u32 {
+        self.a
+    }
+
+    pub fn generic<T: std::fmt::Display>(&self, t: T) -> String {
+        format!("{}{}", t, self.a)
+    }
+
+    pub async fn fetch(&self) -> u32 {
+        self.a
+    }
+}
+
+#[faux::methods]
+impl Greet for Foo {
+    fn greet(&self, name: &str) -> String {
+        format!("{} {}", name, self.a)
+    }
+}
+
+#[faux::create(self_type = "Arc")]
+#[derive(Default)]
+pub struct Shared {}
+
+#[faux::methods(self_type = "Arc")]
+impl Shared {
+    pub fn new() -> Self {
+        Shared {}
+    }
+
+    pub fn arc(self: Arc<Self>) -> Arc<Self> {
+        self
+    }
+
+    pub fn get(&self) -> i32 {
+        1
+    }
+}
+
+#[test]
+fn real_instance() {
+    let mut foo = Foo::new(1);
+    foo.set(2);
+    assert_eq!(foo.get(), 2);
+    assert_eq!(foo.generic("a"), "a2");
+    assert_eq!(foo.greet("hi"), "hi 2");
+    assert_eq!(futures::executor::block_on(foo.fetch()), 2);
+    assert_eq!(Box::new(Foo::new(2)).boxed(), 2);
+    assert_eq!(Rc::new(foo).plus(3), 5);
+
+    let shared = Arc::new(Shared::new()).arc();
+    assert_eq!(shared.get(), 1);
+}
+
+#[test]
+fn mock_instance() {
+    let mut foo = Foo::faux();
+    faux::when!(foo.get).then_return(3);
+    faux::when!(foo.set).then(|_| {});
+    faux::when!(foo.generic).then(|t: &str| format!("mock {}", t));
+    faux::when!(foo.greet).then(|name| format!("hello {}", name));
+    faux::when!(foo.fetch).then_return(4);
+    faux::when!(foo.plus).then(|b| b * 2);
+
+    foo.set(1);
+    assert_eq!(foo.get(), 3);
+    assert_eq!(foo.generic("a"), "mock a");
+    assert_eq!(foo.greet("bob"), "hello bob");
+    assert_eq!(futures::executor::block_on(foo.fetch()), 4);
+    assert_eq!(Rc::new(foo).plus(2), 4);
+
+    let mut shared = Shared::faux();
+    faux::when!(shared.get).then_return(5);
+    assert_eq!(shared.get(), 5);
+}
@@ -0,0 +1,116 @@
+// stands in for a crate that does not export mocks
+mod sdk {
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct Error;
+
+    #[derive(Clone, Default)]
+    pub struct Client {
+        data: HashMap<String, Vec<u8>>,
+    }
+
+    impl Client {
+        pub fn new() -> Self {
+            Client::default()
+        }
+
+        pub fn connect(url: &str) -> Result<Self, Error> {
+            if url.is_empty() {
+                Err(Error)
+            } else {
+                Ok(Client::new())
+            }
+        }
+
+        pub fn get(&self, key: &str) -> Result<Vec<u8>, Error> {
+            self.data.get(key).cloned().ok_or(Error)
+        }
+
+        pub fn set(&mut self, key: String, value: Vec<u8>) {
+            self.data.insert(key, value);
+        }
+
+        pub fn into_keys(self) -> Vec<String> {
+            self.data.into_keys().collect()
+        }
+
+        pub async fn fetch(&self, key: &str) -> Option<Vec<u8>> {
+            self.data.get(key).cloned()
+        }
+    }
+
+    pub trait Named {
+        fn name(&self) -> String;
+    }
+
+    impl Named for Client {
+        fn name(&self) -> String {
+            "sdk".to_owned()
+        }
+    }
+}
+
+faux::mock_foreign! {
+    #[derive(Clone)]
+    pub struct sdk::Client;
+    impl {
+        fn new() -> Self;
+        fn connect(url: &str) -> Result<Self, sdk::Error>;
+        fn get(&self, key: &str) -> Result<Vec<u8>, sdk::Error>;
+        fn set(&mut self, key: String, value: Vec<u8>);
+        fn into_keys(self) -> Vec<String>;
+        async fn fetch(&self, key: &str) -> Option<Vec<u8>>;
+    }
+    impl sdk::Named {
+        fn name(&self) -> String;
+    }
+}
+
+use sdk::Named;
+
+#[test]
+fn real_instance() {
+    let mut client = Client::new();
+    client.set("a".to_owned(), vec![1]);
+    assert_eq!(client.get("a"), Ok(vec![1]));
+    assert_eq!(client.get("b"), Err(sdk::Error));
+    assert_eq!(
+        futures::executor::block_on(client.fetch("a")),
+        Some(vec![1])
+    );
+    assert_eq!(client.name(), "sdk");
+    assert_eq!(client.into_keys(), vec!["a".to_owned()]);
+
+    assert!(Client::connect("").is_err());
+    let connected = Client::connect("url").unwrap();
+    assert_eq!(connected.get("a"), Err(sdk::Error));
+}
+
+#[test]
+fn from_foreign() {
+    let mut foreign = sdk::Client::new();
+    foreign.set("key".to_owned(), vec![3]);
+
+    let client: Client = foreign.into();
+    assert_eq!(client.clone().get("key"), Ok(vec![3]));
+}
+
+#[test]
+fn mock_instance() {
+    let mut client = Client::faux();
+    // the last stub is attempted first
+    faux::when!(client.get).then(|_| Err(sdk::Error));
+    faux::when!(client.get("a")).then(|_| Ok(vec![5]));
+    faux::when!(client.fetch).then_return(None);
+    faux::when!(client.name).then_return("mock".to_owned());
+    faux::when!(client.set).then(|(key, _)| assert_eq!(key, "b"));
+    faux::when!(client.into_keys).then_return(vec![]);
+
+    client.set("b".to_owned(), vec![]);
+    assert_eq!(client.get("a"), Ok(vec![5]));
+    assert_eq!(client.get("b"), Err(sdk::Error));
+    assert_eq!(futures::executor::block_on(client.fetch("a")), None);
+    assert_eq!(client.name(), "mock");
+    assert!(client.into_keys().is_empty());
+}
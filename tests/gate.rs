@@ -0,0 +1,139 @@
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+use futures::task::Poll;
+
+#[faux::create]
+pub struct Service {}
+
+#[faux::methods]
+impl Service {
+    pub fn fetch(&self, id: u32) -> u32 {
+        id
+    }
+
+    pub async fn load(&self) -> String {
+        "real".to_owned()
+    }
+}
+
+#[test]
+fn parks_thread_until_released() {
+    let gate = faux::Gate::new();
+    let mut mock = Service::faux();
+    faux::when!(mock.fetch).then_gated(&gate, 5);
+
+    let mock = Arc::new(mock);
+    let (sender, receiver) = mpsc::channel();
+    let worker = {
+        let mock = mock.clone();
+        thread::spawn(move || sender.send(mock.fetch(1)).unwrap())
+    };
+
+    gate.wait_entered();
+    assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+
+    gate.release();
+    assert_eq!(receiver.recv().unwrap(), 5);
+    worker.join().unwrap();
+}
+
+#[test]
+fn releases_one_call_at_a_time() {
+    let gate = faux::Gate::new();
+    let mut mock = Service::faux();
+    faux::when!(mock.fetch).then_gated(&gate, 5);
+
+    let mock = Arc::new(mock);
+    let (sender, receiver) = mpsc::channel();
+    let workers: Vec<_> = (0..2)
+        .map(|_| {
+            let mock = mock.clone();
+            let sender = sender.clone();
+            thread::spawn(move || sender.send(mock.fetch(1)).unwrap())
+        })
+        .collect();
+
+    gate.wait_entered();
+    gate.wait_entered();
+
+    gate.release();
+    assert_eq!(receiver.recv().unwrap(), 5);
+    assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+
+    gate.release();
+    assert_eq!(receiver.recv().unwrap(), 5);
+    workers.into_iter().for_each(|w| w.join().unwrap());
+}
+
+#[test]
+fn released_before_entering() {
+    let gate = faux::Gate::new();
+    let mut mock = Service::faux();
+    faux::when!(mock.fetch).then_gated(&gate, 5);
+
+    gate.release();
+    assert_eq!(mock.fetch(1), 5);
+    assert!(gate.wait_entered_timeout(Duration::from_millis(10)));
+}
+
+#[test]
+fn wait_entered_times_out() {
+    let gate = faux::Gate::new();
+    assert!(!gate.wait_entered_timeout(Duration::from_millis(10)));
+}
+
+#[test]
+fn limited_times() {
+    let gate = faux::Gate::new();
+    let mut mock = Service::faux();
+    faux::when!(mock.fetch).then_return(1);
+    faux::when!(mock.fetch).once().then_return(2);
+    faux::when!(mock.fetch).times(1).then_gated(&gate, 3);
+
+    gate.release();
+    assert_eq!(mock.fetch(0), 3);
+    assert_eq!(mock.fetch(0), 2);
+    assert_eq!(mock.fetch(0), 1);
+}
+
+#[test]
+fn async_pending_until_released() {
+    let gate = faux::Gate::new();
+    let mut mock = Service::faux();
+    faux::when!(mock.load).then_gated(&gate, "gated".to_owned());
+
+    futures::executor::block_on(async {
+        let load = mock.load();
+        futures::pin_mut!(load);
+
+        assert_eq!(futures::poll!(load.as_mut()), Poll::Pending);
+        gate.entered().await;
+        assert_eq!(futures::poll!(load.as_mut()), Poll::Pending);
+
+        gate.release();
+        assert_eq!(load.await, "gated");
+    });
+}
+
+#[test]
+fn async_released_from_another_thread() {
+    let gate = faux::Gate::new();
+    let mut mock = Service::faux();
+    faux::when!(mock.load).then_gated(&gate, "gated".to_owned());
+
+    let releaser = {
+        let gate = gate.clone();
+        thread::spawn(move || {
+            gate.wait_entered();
+            gate.release();
+        })
+    };
+
+    let loaded = futures::executor::block_on(mock.load());
+    assert_eq!(loaded, "gated");
+    releaser.join().unwrap();
+}
@@ -1,4 +1,4 @@
-#![allow(clippy::disallowed_names)]
+#![allow(clippy::disallowed_names, clippy::extra_unused_type_parameters)]
 
 pub trait MyTrait {}
 
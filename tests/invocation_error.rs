@@ -0,0 +1,227 @@
+use std::panic::{self, AssertUnwindSafe};
+
+#[faux::create]
+pub struct Foo {}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Data {
+    a: i32,
+    b: u32,
+}
+
+#[faux::methods]
+impl Foo {
+    pub fn three_args(&self, _a: i32, _b: &Data, _c: &str) -> u32 {
+        unreachable!()
+    }
+}
+
+#[faux::create]
+#[derive(Clone)]
+pub struct Handled {}
+
+#[faux::methods]
+impl Handled {
+    pub fn collected(&self, _a: i32, _b: &Data) -> u32 {
+        unreachable!()
+    }
+
+    pub fn never_stubbed(&self) {
+        unreachable!()
+    }
+}
+
+fn panic_message(f: impl FnOnce()) -> String {
+    let payload = panic::catch_unwind(AssertUnwindSafe(f)).expect_err("expected a panic");
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast::<&str>().unwrap().to_string(),
+    }
+}
+
+#[test]
+fn closest_stub_first() {
+    let mut mock = Foo::faux();
+    let data = Data { a: 1, b: 2 };
+
+    faux::when!(mock.three_args(1, data.clone(), "a")).then_return(1);
+    faux::when!(mock.three_args(5, _, "hello")).then_return(2);
+    faux::when!(mock.three_args(9, data.clone(), _)).then_return(3);
+
+    let message = panic_message(|| {
+        mock.three_args(5, &Data { a: 0, b: 0 }, "bye");
+    });
+
+    assert!(
+        message.starts_with(
+            "`Foo::three_args` had no suitable stubs. The closest stub (#1) failed because:"
+        ),
+        "{}",
+        message
+    );
+    assert!(
+        message.ends_with(
+            "Other stubs failed because:
+  ✗ #2: Arguments 0, 1 did not match
+  ✗ #0: Arguments 0, 1, 2 did not match"
+        ),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn exhausted_is_closest() {
+    let mut mock = Foo::faux();
+
+    faux::when!(mock.three_args(1, _, _)).once().then_return(1);
+    faux::when!(mock.three_args(2, _, _)).then_return(2);
+
+    let data = Data { a: 1, b: 2 };
+    assert_eq!(mock.three_args(1, &data, "a"), 1);
+
+    let message = panic_message(|| {
+        mock.three_args(1, &data, "a");
+    });
+
+    assert!(
+        message.contains("The closest stub (#0) failed because:\n✗ stub was exhausted"),
+        "{}",
+        message
+    );
+    assert!(
+        message.ends_with("  ✗ #1: Argument 0 did not match"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn diffs_multiline_arguments() {
+    let mut mock = Foo::faux();
+
+    faux::when!(mock.three_args(_, Data { a: 1, b: 2 }, _)).then_return(1);
+
+    let message = panic_message(|| {
+        mock.three_args(3, &Data { a: 1, b: 3 }, "a");
+    });
+
+    assert!(
+        message.ends_with(
+            "  Argument 1 diff (- expected, + actual):
+      Data {
+          a: 1,
+    -     b: 2,
+    +     b: 3,
+      }"
+        ),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn structured_error() {
+    use std::sync::{Arc, Mutex};
+
+    let mut mock = Handled::faux();
+    faux::when!(mock.collected(1, _)).then_return(1);
+
+    let errors = Arc::new(Mutex::new(vec![]));
+    let handler = {
+        let errors = errors.clone();
+        faux::on_unmatched!(mock, move |e| errors.lock().unwrap().push(e.clone()))
+    };
+
+    // the panic happens in another thread but the error is still collected
+    let mock = Arc::new(mock);
+    let worker = {
+        let mock = mock.clone();
+        std::thread::spawn(move || {
+            mock.collected(2, &Data { a: 0, b: 0 });
+        })
+    };
+    assert!(worker.join().is_err());
+    drop(handler);
+
+    let errors = errors.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.struct_name(), "Handled");
+    assert_eq!(error.method(), "collected");
+
+    assert_eq!(error.generics(), "");
+    assert!(!error.never_stubbed());
+
+    let stub_errors: Vec<_> = error.stub_errors().collect();
+    assert_eq!(stub_errors.len(), 1);
+    let (index, stub_error) = stub_errors[0];
+    assert_eq!(index, 0);
+    match stub_error {
+        faux::StubError::NotMatched(mismatch) => {
+            let arguments = mismatch.arguments().unwrap();
+            assert_eq!(arguments.len(), 1);
+            assert_eq!(arguments[0].position(), 0);
+            assert_eq!(arguments[0].expected(), "1");
+            assert_eq!(arguments[0].actual(), "2");
+        }
+        e => panic!("unexpected stub error: {}", e),
+    }
+
+    let source: &dyn std::error::Error = error;
+    assert!(source
+        .to_string()
+        .starts_with("`Handled::collected` had no suitable stubs"));
+}
+
+#[test]
+fn never_stubbed_error() {
+    use std::sync::{Arc, Mutex};
+
+    let mock = Handled::faux();
+
+    let errors = Arc::new(Mutex::new(vec![]));
+    let handler = {
+        let errors = errors.clone();
+        faux::on_unmatched!(mock, move |e| errors.lock().unwrap().push(e.clone()))
+    };
+    panic_message(|| mock.never_stubbed());
+    drop(handler);
+
+    let errors = errors.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].method(), "never_stubbed");
+    assert!(errors[0].never_stubbed());
+    assert!(errors[0].closest().is_none());
+}
+
+#[test]
+fn handlers_are_per_instance() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let mock = Handled::faux();
+    let clone = mock.clone();
+    let other = Handled::faux();
+
+    let reported = Arc::new(AtomicUsize::new(0));
+    let handler = {
+        let reported = reported.clone();
+        faux::on_unmatched!(mock, move |_| {
+            reported.fetch_add(1, Ordering::SeqCst);
+        })
+    };
+
+    panic_message(|| other.never_stubbed());
+    assert_eq!(reported.load(Ordering::SeqCst), 0);
+
+    // clones share the handlers of the instance they were cloned from
+    panic_message(|| clone.never_stubbed());
+    assert_eq!(reported.load(Ordering::SeqCst), 1);
+
+    drop(handler);
+    panic_message(|| mock.never_stubbed());
+    assert_eq!(reported.load(Ordering::SeqCst), 1);
+}
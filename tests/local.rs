@@ -0,0 +1,140 @@
+use std::{cell::RefCell, rc::Rc, thread};
+
+#[faux::create]
+pub struct Foo {}
+
+#[faux::methods]
+impl Foo {
+    pub fn record(&self, _a: u32) -> usize {
+        unreachable!()
+    }
+
+    pub fn shared(&self) -> Rc<String> {
+        unreachable!()
+    }
+
+    pub fn plain(&self) -> u32 {
+        unreachable!()
+    }
+}
+
+#[test]
+fn captures_non_send_state() {
+    let mut mock = Foo::faux();
+
+    let seen = Rc::new(RefCell::new(vec![]));
+    let recorder = seen.clone();
+    faux::when!(mock.record).then_local(move |a| {
+        recorder.borrow_mut().push(a);
+        recorder.borrow().len()
+    });
+
+    assert_eq!(mock.record(3), 1);
+    assert_eq!(mock.record(5), 2);
+    assert_eq!(*seen.borrow(), vec![3, 5]);
+}
+
+#[test]
+fn returns_non_send_values() {
+    let mut mock = Foo::faux();
+
+    let value = Rc::new("hello".to_owned());
+    faux::when!(mock.shared).then_local_return(value.clone());
+    assert_eq!(mock.shared(), value);
+    assert_eq!(mock.shared(), value);
+}
+
+#[test]
+fn once() {
+    let mut mock = Foo::faux();
+
+    let value = Rc::new("hello".to_owned());
+    faux::when!(mock.shared)
+        .once()
+        .then_local_return(value.clone());
+    faux::when!(mock.record(1)).once().then_local({
+        let value = value.clone();
+        move |_| Rc::strong_count(&value)
+    });
+
+    assert_eq!(mock.record(1), 3);
+    assert_eq!(mock.shared(), value);
+    assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn panics_on_other_threads() {
+    let mut mock = Foo::faux();
+    faux::when!(mock.record).then_local(|_| 0);
+
+    let panic = thread::spawn(move || mock.record(1)).join().unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(
+        message.contains("stub #0 of 'Foo::record' is local to the thread that added it"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn calls_from_other_threads_do_not_use_up_the_stub() {
+    let mut mock = Foo::faux();
+    faux::when!(mock.record).times(1).then_local(|a| a as usize);
+
+    thread::scope(|scope| {
+        let other = scope.spawn(|| mock.record(1)).join();
+        assert!(other.is_err());
+    });
+
+    assert_eq!(mock.record(2), 2);
+}
+
+#[test]
+fn other_stubs_may_be_called_from_other_threads() {
+    let mut mock = Foo::faux();
+    faux::when!(mock.record).then_local(|_| 0);
+    faux::when!(mock.plain).then_return(4);
+
+    let plain = thread::spawn(move || mock.plain()).join().unwrap();
+    assert_eq!(plain, 4);
+}
+
+#[test]
+fn leaks_when_dropped_on_other_threads() {
+    let mut mock = Foo::faux();
+
+    let value = Rc::new("hello".to_owned());
+    faux::when!(mock.shared).then_local_return(value.clone());
+
+    thread::spawn(move || drop(mock)).join().unwrap();
+    // the stub was not dropped from the other thread
+    assert_eq!(Rc::strong_count(&value), 2);
+}
+
+#[test]
+fn dropped_on_its_thread() {
+    let mut mock = Foo::faux();
+
+    let value = Rc::new("hello".to_owned());
+    faux::when!(mock.shared).then_local_return(value.clone());
+
+    drop(mock);
+    assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+#[should_panic(expected = "the stub was called again from within itself")]
+fn reentrant() {
+    let mock = Rc::new(RefCell::new(None::<Foo>));
+
+    let mut foo = Foo::faux();
+    let inner = mock.clone();
+    faux::when!(foo.record).then_local(move |a| match a {
+        0 => 0,
+        _ => inner.borrow().as_ref().unwrap().record(a - 1),
+    });
+    *mock.borrow_mut() = Some(foo);
+
+    let foo = mock.borrow();
+    foo.as_ref().unwrap().record(1);
+}
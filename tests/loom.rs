@@ -0,0 +1,83 @@
+#![cfg(all(feature = "loom", loom))]
+
+use loom::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+#[faux::create]
+pub struct Counter {}
+
+#[faux::methods]
+impl Counter {
+    pub fn next(&self) -> usize {
+        0
+    }
+}
+
+#[test]
+fn concurrent_invocations() {
+    loom::model(|| {
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut counter = Counter::faux();
+        {
+            let count = count.clone();
+            faux::when!(counter.next).then(move |_| count.fetch_add(1, Ordering::SeqCst));
+        }
+
+        let counter = Arc::new(counter);
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let counter = counter.clone();
+                thread::spawn(move || counter.next())
+            })
+            .collect();
+
+        let mut seen: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        seen.sort_unstable();
+        assert_eq!(seen, [0, 1]);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    });
+}
+
+#[test]
+fn once_is_called_once() {
+    loom::model(|| {
+        let mut counter = Counter::faux();
+        faux::when!(counter.next).then_return(1);
+        faux::when!(counter.next).once().then_return(2);
+
+        let counter = Arc::new(counter);
+        let other = {
+            let counter = counter.clone();
+            thread::spawn(move || counter.next())
+        };
+
+        let mut seen = [counter.next(), other.join().unwrap()];
+        seen.sort_unstable();
+        assert_eq!(seen, [1, 2]);
+        assert_eq!(faux::calls!(counter.next).completed(), 2);
+    });
+}
+
+#[test]
+fn limited_stub_is_claimed_within_its_limit() {
+    loom::model(|| {
+        let mut counter = Counter::faux();
+        faux::when!(counter.next).then_fn(|_| 1);
+        faux::when!(counter.next).times(1).then_fn(|_| 2);
+
+        let counter = Arc::new(counter);
+        let other = {
+            let counter = counter.clone();
+            thread::spawn(move || counter.next())
+        };
+
+        let mut seen = [counter.next(), other.join().unwrap()];
+        seen.sort_unstable();
+        assert_eq!(seen, [1, 2]);
+    });
+}
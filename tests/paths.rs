@@ -55,6 +55,37 @@ mod other {
     }
 }
 
+mod db {
+    pub mod pool {
+        #[faux::create]
+        pub struct Pool {
+            size: usize,
+        }
+
+        mod impls {
+            #[faux::methods]
+            impl super::Pool {
+                pub fn new(size: usize) -> super::Pool {
+                    super::Pool { size }
+                }
+
+                pub fn size(&self) -> usize {
+                    self.size
+                }
+            }
+        }
+    }
+}
+
+mod stats {
+    #[faux::methods]
+    impl crate::db::pool::Pool {
+        pub fn doubled(&self) -> crate::db::pool::Pool {
+            crate::db::pool::Pool::new(self.size() * 2)
+        }
+    }
+}
+
 #[faux::methods]
 impl bar::Bar {
     pub fn add(&self) -> i32 {
@@ -74,6 +105,20 @@ fn real() {
     assert_eq!(bar.add(), 14);
 }
 
+#[test]
+fn rooted_paths() {
+    use crate::db::pool::Pool;
+
+    let pool = Pool::new(3);
+    assert_eq!(pool.doubled().size(), 6);
+
+    let mut mock = Pool::faux();
+    faux::when!(mock.size).then_return(5);
+    faux::when!(mock.doubled).then(|_| Pool::new(1));
+    assert_eq!(mock.size(), 5);
+    assert_eq!(mock.doubled().size(), 1);
+}
+
 #[test]
 fn mocked() {
     use crate::{bar::Bar, foo::Foo};
@@ -0,0 +1,52 @@
+mod service {
+    #[faux::create]
+    pub struct Service {
+        base: u32,
+    }
+
+    #[faux::methods]
+    impl Service {
+        pub fn new(base: u32) -> Self {
+            Service { base }
+        }
+
+        pub fn total(&self, a: u32) -> u32 {
+            self.scaled(a) + self.offset()
+        }
+
+        fn scaled(&self, a: u32) -> u32 {
+            a * self.base
+        }
+
+        pub(crate) fn offset(&self) -> u32 {
+            1
+        }
+    }
+
+    #[test]
+    fn real_instance() {
+        let service = Service::new(2);
+        assert_eq!(service.scaled(3), 6);
+        assert_eq!(service.total(3), 7);
+    }
+
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn stubs_private_method() {
+            let mut service = Service::faux();
+            faux::when!(service.scaled(3)).then_return(10);
+
+            assert_eq!(service.scaled(3), 10);
+        }
+    }
+}
+
+#[test]
+fn stubs_crate_visible_method() {
+    let mut service = service::Service::faux();
+    faux::when!(service.offset).then_return(5);
+
+    assert_eq!(service.offset(), 5);
+}
@@ -0,0 +1,111 @@
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+    Ready,
+    Busy,
+}
+
+pub trait Describe {
+    fn id(&self) -> u32;
+
+    fn describe(&self) -> String;
+}
+
+#[faux::create]
+pub struct Worker {
+    status: Status,
+    id: u32,
+}
+
+#[faux::methods]
+impl Worker {
+    pub fn new(status: Status) -> Self {
+        Worker { status, id: 1 }
+    }
+
+    pub fn status(&self) -> Status {
+        self.status.clone()
+    }
+
+    pub fn jobs(&self, limit: usize) -> Vec<u32> {
+        (0..limit as u32).collect()
+    }
+
+    #[faux::real]
+    pub fn is_ready(&self) -> bool {
+        self.status() == Status::Ready
+    }
+
+    #[faux::real]
+    pub fn first_job(&self) -> Option<u32> {
+        self.jobs(1).first().copied()
+    }
+
+    #[faux::real]
+    pub async fn ready_later(&self) -> bool {
+        self.is_ready()
+    }
+
+    // real methods may call #[faux::real] methods too
+    pub fn summary(&self) -> String {
+        format!("ready: {}", self.is_ready())
+    }
+}
+
+#[faux::methods]
+impl Describe for Worker {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    #[faux::real]
+    fn describe(&self) -> String {
+        format!("worker #{}", self.id())
+    }
+}
+
+#[test]
+fn real_instance() {
+    let worker = Worker::new(Status::Ready);
+    assert!(worker.is_ready());
+    assert_eq!(worker.first_job(), Some(0));
+    assert!(futures::executor::block_on(worker.ready_later()));
+    assert_eq!(worker.summary(), "ready: true");
+    assert_eq!(worker.describe(), "worker #1");
+}
+
+#[test]
+fn mock_instance() {
+    let mut worker = Worker::faux();
+    faux::when!(worker.status).then_return(Status::Busy);
+    faux::when!(worker.jobs(1)).then_return(vec![7]);
+    faux::when!(worker.id).then_return(3);
+
+    assert!(!worker.is_ready());
+    assert_eq!(worker.first_job(), Some(7));
+    assert!(!futures::executor::block_on(worker.ready_later()));
+    assert_eq!(worker.describe(), "worker #3");
+}
+
+mod imported {
+    // a bare #[real] may belong to another crate so it is not treated
+    // as #[faux::real]
+    use faux::real;
+
+    #[faux::create]
+    pub struct Counter {}
+
+    #[faux::methods]
+    impl Counter {
+        #[real]
+        pub fn count(&self) -> u32 {
+            1
+        }
+    }
+
+    #[test]
+    fn bare_attribute_is_mocked() {
+        let mut counter = Counter::faux();
+        faux::when!(counter.count).then_return(5);
+        assert_eq!(counter.count(), 5);
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    cell::Cell,
+    panic,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+#[faux::create]
+#[derive(Clone)]
+pub struct Service {}
+
+#[faux::methods]
+impl Service {
+    pub fn get(&self, id: u32) -> u32 {
+        id
+    }
+
+    pub fn double(&self, id: u32) -> u32 {
+        self.get(id) * 2
+    }
+
+    pub async fn fetch(&self) -> u32 {
+        0
+    }
+}
+
+// a handle to the mock that stubs can call back into once set
+#[derive(Clone, Default)]
+struct Handle(Arc<Mutex<Option<Service>>>);
+
+impl Handle {
+    fn set(&self, service: Service) {
+        *self.0.lock().unwrap() = Some(service);
+    }
+
+    fn get(&self) -> Service {
+        self.0.lock().unwrap().clone().unwrap()
+    }
+}
+
+#[test]
+fn stub_calls_another_method() {
+    let mut service = Service::faux();
+    let inner = Handle::default();
+    faux::when!(service.get).then(|id| id + 1);
+    {
+        let inner = inner.clone();
+        faux::when!(service.double).then(move |id| inner.get().get(id) * 2);
+    }
+    inner.set(service.clone());
+
+    assert_eq!(service.double(2), 6);
+}
+
+#[test]
+fn stub_calls_another_stub_of_the_same_method() {
+    let mut service = Service::faux();
+    let inner = Handle::default();
+    faux::when!(service.get(0)).then_return(1);
+    {
+        let inner = inner.clone();
+        faux::when!(service.get(5)).then(move |_| inner.get().get(0) + 10);
+    }
+    inner.set(service.clone());
+
+    assert_eq!(service.get(5), 11);
+}
+
+#[test]
+#[should_panic(expected = "faux: the stub was called again from within itself")]
+fn stub_calls_itself() {
+    let mut service = Service::faux();
+    let inner = Handle::default();
+    {
+        let inner = inner.clone();
+        faux::when!(service.get).then(move |id| match id {
+            0 => 0,
+            n => inner.get().get(n - 1),
+        });
+    }
+    inner.set(service.clone());
+
+    service.get(1);
+}
+
+#[test]
+fn other_threads_are_not_blocked_by_a_running_stub() {
+    let (entered_sender, entered) = mpsc::channel();
+    let (release, released) = mpsc::channel::<()>();
+
+    let mut service = Service::faux();
+    faux::when!(service.get(2)).then_return(20);
+    faux::when!(service.get(1)).then(move |_| {
+        entered_sender.send(()).unwrap();
+        released.recv().unwrap();
+        10
+    });
+
+    let service = Arc::new(service);
+    let blocked = {
+        let service = service.clone();
+        thread::spawn(move || service.get(1))
+    };
+
+    entered.recv().unwrap();
+    assert_eq!(service.get(2), 20);
+
+    release.send(()).unwrap();
+    assert_eq!(blocked.join().unwrap(), 10);
+}
+
+#[derive(Debug, PartialEq)]
+struct Boom(u32);
+
+#[test]
+fn panic_payload_is_kept() {
+    let mut service = Service::faux();
+    faux::when!(service.get).then_return(1);
+    faux::when!(service.get(3)).then(|_| panic::panic_any(Boom(3)));
+
+    let panic = panic::catch_unwind(|| service.get(3)).unwrap_err();
+    assert_eq!(panic.downcast_ref::<Boom>(), Some(&Boom(3)));
+}
+
+#[test]
+fn panic_hook_runs_once() {
+    thread_local! {
+        static PANICS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    let mut service = Service::faux();
+    faux::when!(service.get).then(|_| panic!("boom"));
+
+    // other tests may panic at the same time so only this thread's
+    // panics are counted
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {
+        PANICS.with(|panics| panics.set(panics.get() + 1))
+    }));
+    let panic = panic::catch_unwind(|| service.get(3));
+    panic::set_hook(hook);
+
+    assert!(panic.is_err());
+    assert_eq!(PANICS.with(Cell::get), 1);
+}
+
+#[test]
+fn mock_is_usable_after_a_stub_panics() {
+    let mut service = Service::faux();
+    faux::when!(service.get).then(|id| {
+        if id == 0 {
+            panic!("zero");
+        }
+        id
+    });
+
+    assert!(panic::catch_unwind(|| service.get(0)).is_err());
+    assert_eq!(service.get(4), 4);
+    assert_eq!(service.get(5), 5);
+}
+
+#[test]
+fn panicking_async_stub_keeps_its_payload() {
+    let mut service = Service::faux();
+    faux::when!(service.fetch).then_async(|_| async { panic!("async boom") });
+
+    let panic = panic::catch_unwind(|| futures::executor::block_on(service.fetch())).unwrap_err();
+    assert_eq!(
+        panic.downcast_ref::<String>().unwrap(),
+        "async boom\nfaux: the panic came from stub #0 of 'Service::fetch'"
+    );
+}
+
+#[test]
+fn panic_names_the_stub() {
+    let mut service = Service::faux();
+    faux::when!(service.get).then_return(1);
+    faux::when!(service.get(3)).then(|id| panic!("bad id: {}", id));
+
+    let panic = panic::catch_unwind(|| service.get(3)).unwrap_err();
+    assert_eq!(
+        panic.downcast_ref::<String>().unwrap(),
+        "bad id: 3\nfaux: the panic came from stub #1 of 'Service::get'"
+    );
+}
+
+#[test]
+fn nested_panic_names_the_inner_stub_once() {
+    let mut service = Service::faux();
+    let inner = Handle::default();
+    faux::when!(service.get).then(|_| panic!("boom"));
+    {
+        let inner = inner.clone();
+        faux::when!(service.double).then(move |id| inner.get().get(id) * 2);
+    }
+    inner.set(service.clone());
+
+    let panic = panic::catch_unwind(|| service.double(3)).unwrap_err();
+    assert_eq!(
+        panic.downcast_ref::<String>().unwrap(),
+        "boom\nfaux: the panic came from stub #0 of 'Service::get'"
+    );
+}
@@ -0,0 +1,202 @@
+#![allow(clippy::len_without_is_empty)]
+
+use std::io::{self, Read};
+
+pub trait MyReader {
+    fn read(&self) -> String;
+}
+
+pub trait Len {
+    fn len(&self) -> usize;
+}
+
+pub trait Named<T> {
+    fn name(&self) -> T;
+}
+
+pub trait Convert<T> {
+    fn convert(&self) -> T;
+}
+
+pub trait Greet {
+    fn greet(&self) -> String;
+}
+
+mod other {
+    pub trait Len {
+        fn len(&self) -> usize;
+    }
+}
+
+// a trait with the same name as another one
+use other::Len as OtherLen;
+
+#[faux::create]
+pub struct Source {
+    data: Vec<u8>,
+}
+
+#[faux::methods]
+impl Source {
+    pub fn new(data: Vec<u8>) -> Self {
+        Source { data }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+}
+
+#[faux::methods]
+impl Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.data.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        Ok(len)
+    }
+}
+
+#[faux::methods]
+impl MyReader for Source {
+    fn read(&self) -> String {
+        String::from_utf8(self.data.clone()).unwrap()
+    }
+}
+
+#[faux::methods]
+impl Len for Source {
+    fn len(&self) -> usize {
+        self.data.len() * 2
+    }
+}
+
+#[faux::methods]
+impl Named<u8> for Source {
+    fn name(&self) -> u8 {
+        self.data[0]
+    }
+}
+
+#[faux::methods]
+impl OtherLen for Source {
+    fn len(&self) -> usize {
+        self.data.len() * 3
+    }
+}
+
+#[faux::methods]
+impl Convert<u8> for Source {
+    fn convert(&self) -> u8 {
+        self.data[0]
+    }
+}
+
+#[faux::methods]
+impl Convert<String> for Source {
+    fn convert(&self) -> String {
+        String::from_utf8(self.data.clone()).unwrap()
+    }
+}
+
+mod greet {
+    use super::Greet;
+
+    #[faux::methods]
+    impl Greet for super::Source {
+        fn greet(&self) -> String {
+            "hello".to_owned()
+        }
+    }
+}
+
+#[test]
+fn real_instance() {
+    let mut source = Source::new(b"hi".to_vec());
+    let mut buf = [0; 4];
+    assert_eq!(Read::read(&mut source, &mut buf).unwrap(), 2);
+    assert_eq!(MyReader::read(&source), "hi");
+    assert_eq!(source.len(), 2);
+    assert_eq!(Len::len(&source), 4);
+    assert_eq!(source.name(), b'h');
+}
+
+#[test]
+fn stub_by_trait() {
+    let mut mock = Source::faux();
+    faux::when!(<mock as Read>.read(_)).then(|buf| Ok(buf.len()));
+    faux::when!(<mock as MyReader>.read).then_return("mock".to_owned());
+    faux::when!(<mock as Len>.len()).then_return(10);
+    faux::when!(<mock as Named<u8>>.name).then_return(7);
+
+    let mut buf = [0; 3];
+    assert_eq!(Read::read(&mut mock, &mut buf).unwrap(), 3);
+    assert_eq!(MyReader::read(&mock), "mock");
+    assert_eq!(Len::len(&mock), 10);
+    assert_eq!(mock.name(), 7);
+}
+
+#[test]
+fn inherent_method_takes_precedence() {
+    let mut mock = Source::faux();
+    faux::when!(mock.len).then_return(1);
+    faux::when!(<mock as Len>.len).then_return(2);
+
+    assert_eq!(mock.len(), 1);
+    assert_eq!(Len::len(&mock), 2);
+}
+
+#[test]
+fn unambiguous_trait_method() {
+    let mut mock = Source::faux();
+    faux::when!(mock.name).then_return(3);
+    assert_eq!(mock.name(), 3);
+}
+
+#[test]
+fn generic_arguments() {
+    let mut mock = Source::faux();
+    faux::when!(<mock as Convert<u8>>.convert).then_return(4);
+    faux::when!(<mock as Convert<String>>.convert).then_return("four".to_owned());
+
+    assert_eq!(Convert::<u8>::convert(&mock), 4);
+    assert_eq!(Convert::<String>::convert(&mock), "four");
+}
+
+#[test]
+fn renamed_trait() {
+    let mut mock = Source::faux();
+    faux::when!(<mock as Len>.len).then_return(2);
+    faux::when!(<mock as OtherLen>.len).then_return(3);
+
+    assert_eq!(Len::len(&mock), 2);
+    assert_eq!(OtherLen::len(&mock), 3);
+}
+
+#[test]
+fn trait_method_from_another_module() {
+    let mut mock = Source::faux();
+    faux::when!(<mock as Greet>.greet).then_return("hi".to_owned());
+    assert_eq!(mock.greet(), "hi");
+}
+
+#[test]
+fn paths_are_ignored() {
+    let mut mock = Source::faux();
+    faux::when!(<mock as io::Read>.read(_)).then(|buf| Ok(buf.len()));
+    faux::when!(<mock as self::Convert<std::string::String>>.convert)
+        .then_return("four".to_owned());
+
+    assert_eq!(Read::read(&mut mock, &mut [0; 2]).unwrap(), 2);
+    assert_eq!(Convert::<String>::convert(&mock), "four");
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unambiguous_trait_method_in_child_module() {
+        let mut mock = Source::faux();
+        faux::when!(mock.name).then_return(5);
+        assert_eq!(mock.name(), 5);
+    }
+}
@@ -0,0 +1,134 @@
+#![cfg(feature = "serde")]
+
+use serde::{Deserialize, Serialize};
+
+#[faux::create]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    base_url: String,
+    #[serde(default)]
+    retries: u32,
+}
+
+#[faux::methods]
+impl Config {
+    pub fn new(base_url: String, retries: u32) -> Self {
+        Config { base_url, retries }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+}
+
+#[faux::create(self_type = "Rc")]
+#[derive(Serialize, Deserialize)]
+pub struct Shared {
+    name: String,
+}
+
+#[faux::methods(self_type = "Rc")]
+impl Shared {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+// has its own `serialize` method next to the derived one
+#[faux::create]
+#[derive(Serialize)]
+pub struct Encoder {
+    prefix: String,
+}
+
+#[faux::methods]
+impl Encoder {
+    pub fn new(prefix: &str) -> Self {
+        Encoder {
+            prefix: prefix.to_owned(),
+        }
+    }
+
+    pub fn serialize(&self, value: u32) -> String {
+        format!("{}{}", self.prefix, value)
+    }
+}
+
+#[test]
+fn real_round_trip() {
+    let config = Config::new("https://example.com".to_owned(), 3);
+
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(json, r#"{"baseUrl":"https://example.com","retries":3}"#);
+
+    let config: Config = serde_json::from_str(&json).unwrap();
+    assert_eq!(config.base_url(), "https://example.com");
+    assert_eq!(config.retries(), 3);
+}
+
+#[test]
+fn deserializes_real_instances() {
+    let config: Config = serde_json::from_str(r#"{"baseUrl":"https://example.com"}"#).unwrap();
+    assert_eq!(config.retries(), 0);
+
+    let shared: Shared = serde_json::from_str(r#"{"name":"foo"}"#).unwrap();
+    assert_eq!(shared.name(), "foo");
+    assert_eq!(serde_json::to_string(&shared).unwrap(), r#"{"name":"foo"}"#);
+}
+
+#[test]
+fn stubbed_serialize() {
+    let mut mock = Config::faux();
+    faux::when!(mock.retries).then_return(5);
+    faux::when!(<mock as Serialize>.serialize)
+        .then(|_| Config::new("https://mock.com".to_owned(), 1));
+
+    assert_eq!(mock.retries(), 5);
+    let json = serde_json::to_string(&mock).unwrap();
+    assert_eq!(json, r#"{"baseUrl":"https://mock.com","retries":1}"#);
+}
+
+#[test]
+fn unstubbed_serialize() {
+    let mock = Config::faux();
+
+    let error = serde_json::to_string(&mock).unwrap_err();
+    assert!(
+        error.to_string().contains(
+            "a mock instance of `Config` cannot be serialized unless `serialize` is stubbed"
+        ),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn substitute_must_be_real() {
+    let mut mock = Config::faux();
+    faux::when!(<mock as Serialize>.serialize).then(|_| Config::faux());
+
+    let error = serde_json::to_string(&mock).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("a mock instance of `Config` cannot be serialized"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn serialize_method() {
+    let mut mock = Encoder::faux();
+    faux::when!(mock.serialize(3)).then_return("mock 3".to_owned());
+    faux::when!(<mock as Serialize>.serialize).then(|_| Encoder::new("real "));
+
+    assert_eq!(mock.serialize(3), "mock 3");
+    let json = serde_json::to_string(&mock).unwrap();
+    assert_eq!(json, r#"{"prefix":"real "}"#);
+}
@@ -79,7 +79,7 @@ fn faux_ref_output() {
 #[test]
 fn faux_ref_wrapped_output() {
     let mut mock = Foo::faux();
-    unsafe { faux::when!(mock.ret_wrapped).then_unchecked(|a| Some(a)) };
+    unsafe { faux::when!(mock.ret_wrapped).then_unchecked(Some) };
     unsafe { faux::when!(mock.ret_wrapped_twice).then_unchecked(|a| Some(Some(a))) };
     let x = 30 + 30;
     assert_eq!(*mock.ret_wrapped(&x).unwrap(), 60);
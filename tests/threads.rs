@@ -111,6 +111,76 @@ fn mutex_does_not_lock_entire_mock() {
         .expect("a deadlock occurred!");
 }
 
+#[test]
+fn fn_stub_is_not_locked_while_running() {
+    // calls fake.bar() in two separate threads, each waiting for the
+    // other to start before finishing
+    // this asserts that a `Fn` stub may run in both threads at once
+
+    let mut fake = Foo::faux();
+
+    let started = Arc::new(AtomicUsize::new(0));
+    faux::when!(fake.bar).then_fn(move |_| {
+        started.fetch_add(1, Ordering::SeqCst);
+        spin_until(&started, 2);
+    });
+
+    let fake = Arc::new(fake);
+    let start_thread = || {
+        let fake = fake.clone();
+        std::thread::spawn(move || fake.bar())
+    };
+
+    let thread_1 = start_thread();
+    let thread_2 = start_thread();
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        thread_1.join().unwrap();
+        thread_2.join().unwrap();
+        sender.send(()).unwrap();
+    });
+
+    receiver
+        .recv_timeout(Duration::from_millis(100))
+        .expect("a deadlock occurred!");
+}
+
+#[test]
+fn limited_stubs_are_not_exceeded_across_threads() {
+    let mut fake = Foo::faux();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    {
+        let calls = calls.clone();
+        faux::when!(fake.bar).times(8_000).then_fn(move |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+
+    let fake = Arc::new(fake);
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let fake = fake.clone();
+            std::thread::spawn(move || {
+                for _ in 0..1_000 {
+                    fake.bar();
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 8_000);
+    let exhausted =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| fake.bar())).unwrap_err();
+    let message = exhausted.downcast_ref::<String>().unwrap();
+    assert!(message.contains("stub was exhausted"), "{}", message);
+}
+
 fn spin_until(a: &Arc<AtomicUsize>, val: usize) {
     loop {
         if a.load(Ordering::SeqCst) == val {
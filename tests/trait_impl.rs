@@ -80,3 +80,25 @@ fn generic_trait() {
     faux::when!(faux.g_method).then(|_| {});
     faux.g_method();
 }
+
+// multiple impl blocks for the same struct in the same mod
+#[faux::methods]
+impl MyStruct {
+    fn one(&self) -> u8 {
+        1
+    }
+}
+
+#[faux::methods]
+impl MyStruct {
+    fn two(&self) -> u8 {
+        2
+    }
+}
+
+#[test]
+fn multiple_impl_blocks() {
+    let my_struct = MyStruct::new();
+    assert_eq!(my_struct.one(), 1);
+    assert_eq!(my_struct.two(), 2);
+}
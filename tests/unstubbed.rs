@@ -0,0 +1,247 @@
+use std::{fmt, io};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Unstubbed(faux::Unstubbed),
+    Other(String),
+}
+
+impl From<faux::Unstubbed> for Error {
+    fn from(unstubbed: faux::Unstubbed) -> Self {
+        Error::Unstubbed(unstubbed)
+    }
+}
+
+#[derive(Debug)]
+pub enum Failure {
+    Unstubbed(faux::Unstubbed),
+    Unmatched(faux::InvocationError),
+}
+
+impl From<faux::Unstubbed> for Failure {
+    fn from(unstubbed: faux::Unstubbed) -> Self {
+        Failure::Unstubbed(unstubbed)
+    }
+}
+
+impl From<faux::InvocationError> for Failure {
+    fn from(error: faux::InvocationError) -> Self {
+        Failure::Unmatched(error)
+    }
+}
+
+#[faux::create]
+pub struct Foo {}
+
+#[faux::methods(unstubbed = "err")]
+impl Foo {
+    pub fn fetch(&self, _id: u32) -> Result<u32, Error> {
+        unreachable!()
+    }
+
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        unreachable!()
+    }
+
+    pub async fn fetch_async(&self) -> Result<String, Error> {
+        unreachable!()
+    }
+
+    pub fn generic<T: Default>(&self) -> Result<T, Error> {
+        unreachable!()
+    }
+
+    pub fn count(&self) -> u32 {
+        unreachable!()
+    }
+
+    pub fn parse(&self) -> Result<u32, String> {
+        unreachable!()
+    }
+
+    pub fn format(&self) -> fmt::Result {
+        unreachable!()
+    }
+
+    pub fn send(&self, _data: u32) -> Result<(), Failure> {
+        unreachable!()
+    }
+
+    pub fn boxed(&self, _data: u32) -> Result<(), Box<dyn std::error::Error>> {
+        unreachable!()
+    }
+}
+
+#[test]
+fn unstubbed_returns_err() {
+    let mock = Foo::faux();
+
+    match mock.fetch(3) {
+        Err(Error::Unstubbed(unstubbed)) => {
+            assert_eq!(unstubbed.struct_name(), "Foo");
+            assert_eq!(unstubbed.method(), "fetch");
+            assert_eq!(unstubbed.generics(), "");
+            assert_eq!(
+                unstubbed.to_string(),
+                "`Foo::fetch` was called but never stubbed"
+            );
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn io_result() {
+    let mock = Foo::faux();
+    let error = mock.read().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "`Foo::read` was called but never stubbed"
+    );
+}
+
+#[test]
+fn async_method() {
+    let mock = Foo::faux();
+    let result = futures::executor::block_on(mock.fetch_async());
+    assert!(matches!(result, Err(Error::Unstubbed(_))));
+}
+
+#[test]
+fn generic_method() {
+    let mock = Foo::faux();
+    match mock.generic::<i32>() {
+        Err(Error::Unstubbed(unstubbed)) => assert_eq!(unstubbed.generics(), "T"),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn stubs_still_work() {
+    let mut mock = Foo::faux();
+    faux::when!(mock.fetch(1)).then_return(Ok(10));
+    faux::when!(mock.fetch(2)).then(|_| Err(Error::Other("nope".to_string())));
+
+    assert_eq!(mock.fetch(1), Ok(10));
+    assert_eq!(mock.fetch(2), Err(Error::Other("nope".to_string())));
+}
+
+#[test]
+#[should_panic(expected = "`Foo::fetch` had no suitable stubs")]
+fn unmatched_stubs_panic() {
+    let mut mock = Foo::faux();
+    faux::when!(mock.fetch(1)).then_return(Ok(10));
+    let _ = mock.fetch(2);
+}
+
+#[test]
+#[should_panic(expected = "`Foo::count` was called but never stubbed")]
+fn non_result_panics() {
+    let mock = Foo::faux();
+    mock.count();
+}
+
+#[test]
+#[should_panic(expected = "`Foo::parse` was called but never stubbed")]
+fn error_not_from_unstubbed_panics() {
+    let mock = Foo::faux();
+    let _ = mock.parse();
+}
+
+#[test]
+#[should_panic(expected = "`Foo::format` was called but never stubbed")]
+fn result_alias_without_unstubbed_panics() {
+    let mock = Foo::faux();
+    let _ = mock.format();
+}
+
+#[test]
+#[should_panic(expected = "`Foo::send` had no suitable stubs")]
+fn unmatched_panics_even_if_error_converts() {
+    let mut mock = Foo::faux();
+    faux::when!(mock.send(1)).then(|_| Ok(()));
+    let _ = mock.send(2);
+}
+
+#[test]
+fn unstubbed_prefers_unstubbed_error() {
+    let mock = Foo::faux();
+    assert!(matches!(mock.send(1), Err(Failure::Unstubbed(_))));
+}
+
+#[faux::create]
+pub struct Bar {}
+
+#[faux::methods(unmatched = "err")]
+impl Bar {
+    pub fn send(&self, _data: u32) -> Result<(), Failure> {
+        unreachable!()
+    }
+
+    pub fn boxed(&self, _data: u32) -> Result<(), Box<dyn std::error::Error>> {
+        unreachable!()
+    }
+
+    pub fn count(&self, _data: u32) -> u32 {
+        unreachable!()
+    }
+}
+
+#[test]
+fn unmatched_returns_err() {
+    let mut mock = Bar::faux();
+    faux::when!(mock.send(1)).then(|_| Ok(()));
+
+    assert!(mock.send(1).is_ok());
+    match mock.send(2) {
+        Err(Failure::Unmatched(error)) => {
+            assert_eq!(error.method(), "send");
+            assert!(!error.never_stubbed());
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+#[should_panic(expected = "`Bar::send` was called but never stubbed")]
+fn unmatched_err_does_not_cover_unstubbed() {
+    let mock = Bar::faux();
+    let _ = mock.send(1);
+}
+
+#[test]
+#[should_panic(expected = "`Bar::count` had no suitable stubs")]
+fn unmatched_non_result_panics() {
+    let mut mock = Bar::faux();
+    faux::when!(mock.count(1)).then_return(1);
+    mock.count(2);
+}
+
+#[test]
+fn unmatched_boxed_error() {
+    let mut mock = Bar::faux();
+    faux::when!(mock.boxed(1)).then(|_| Ok(()));
+
+    let error = mock.boxed(2).unwrap_err();
+    let error = error.downcast_ref::<faux::InvocationError>().unwrap();
+    assert!(error
+        .to_string()
+        .starts_with("`Bar::boxed` had no suitable stubs"));
+}
+
+#[test]
+fn unmatched_err_is_reported() {
+    use std::sync::{Arc, Mutex};
+
+    let mut mock = Bar::faux();
+    faux::when!(mock.send(1)).then(|_| Ok(()));
+
+    let errors = Arc::new(Mutex::new(vec![]));
+    let _handler = {
+        let errors = errors.clone();
+        faux::on_unmatched!(mock, move |e| errors.lock().unwrap().push(e.clone()))
+    };
+
+    assert!(mock.send(2).is_err());
+    assert_eq!(errors.lock().unwrap().len(), 1);
+}
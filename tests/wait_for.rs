@@ -0,0 +1,196 @@
+use std::{sync::Arc, thread, time::Duration};
+
+#[faux::create]
+pub struct Publisher {}
+
+#[faux::methods]
+impl Publisher {
+    pub fn publish(&self, topic: &str) -> bool {
+        !topic.is_empty()
+    }
+
+    pub async fn flush(&self) {}
+}
+
+pub trait Sink {
+    fn publish(&self, count: u32);
+}
+
+#[faux::methods]
+impl Sink for Publisher {
+    fn publish(&self, _count: u32) {}
+}
+
+fn spawn_publish(publisher: &Arc<Publisher>, topics: &[&'static str]) {
+    for &topic in topics {
+        let publisher = publisher.clone();
+        thread::spawn(move || publisher.publish(topic));
+    }
+}
+
+#[test]
+fn waits_for_calls_from_other_threads() {
+    let mut publisher = Publisher::faux();
+    faux::when!(publisher.publish).then_return(true);
+
+    let publisher = Arc::new(publisher);
+    spawn_publish(&publisher, &["a", "b", "c"]);
+
+    faux::wait_for!(
+        publisher.publish(_),
+        times = 3,
+        timeout = Duration::from_secs(5)
+    );
+}
+
+#[test]
+fn counts_previous_calls() {
+    let mut publisher = Publisher::faux();
+    faux::when!(publisher.publish).then_return(true);
+
+    publisher.publish("a");
+    faux::wait_for!(publisher.publish, timeout = Duration::from_millis(10));
+}
+
+#[test]
+fn waits_for_matching_calls() {
+    let mut publisher = Publisher::faux();
+    faux::when!(publisher.publish).then_return(true);
+
+    let publisher = Arc::new(publisher);
+    let waiter = {
+        let publisher = publisher.clone();
+        thread::spawn(move || faux::wait_for!(publisher.publish("news"), times = 2))
+    };
+
+    // the waiter only counts calls made after it started
+    while !waiter.is_finished() {
+        publisher.publish("weather");
+        publisher.publish("news");
+        thread::sleep(Duration::from_millis(1));
+    }
+    waiter.join().unwrap();
+}
+
+#[test]
+fn matches_calls_made_after_creating_the_future() {
+    let mut publisher = Publisher::faux();
+    faux::when!(publisher.publish).then_return(true);
+
+    // the wait starts when the future is created, not when polled
+    let waiting = faux::wait_for!(
+        async publisher.publish("news"),
+        times = 2,
+        timeout = Duration::from_secs(5)
+    );
+    publisher.publish("weather");
+    publisher.publish("news");
+    publisher.publish("news");
+
+    futures::executor::block_on(waiting);
+}
+
+#[test]
+#[should_panic(
+    expected = "1 call(s) made before the wait started were not checked against the arguments"
+)]
+fn reports_unchecked_previous_calls() {
+    let mut publisher = Publisher::faux();
+    faux::when!(publisher.publish).then_return(true);
+
+    publisher.publish("news");
+    faux::wait_for!(
+        publisher.publish("news"),
+        timeout = Duration::from_millis(10)
+    );
+}
+
+#[test]
+fn waits_for_trait_methods() {
+    let mut publisher = Publisher::faux();
+    faux::when!(<publisher as Sink>.publish).then_return(());
+
+    let publisher = Arc::new(publisher);
+    {
+        let publisher = publisher.clone();
+        thread::spawn(move || Sink::publish(&*publisher, 3));
+    }
+
+    faux::wait_for!(<publisher as Sink>.publish, timeout = Duration::from_secs(5));
+}
+
+#[test]
+fn waits_asynchronously() {
+    let mut publisher = Publisher::faux();
+    faux::when!(publisher.flush).then_return(());
+
+    let publisher = Arc::new(publisher);
+    {
+        let publisher = publisher.clone();
+        thread::spawn(move || futures::executor::block_on(publisher.flush()));
+    }
+
+    futures::executor::block_on(async {
+        faux::wait_for!(async publisher.flush, timeout = Duration::from_secs(5)).await;
+    });
+}
+
+#[test]
+#[should_panic(
+    expected = "timed out after 10ms waiting for 2 call(s) to 'Publisher::publish' but only 1 matched"
+)]
+fn times_out() {
+    let mut publisher = Publisher::faux();
+    faux::when!(publisher.publish).then_return(true);
+
+    publisher.publish("a");
+    faux::wait_for!(
+        publisher.publish,
+        times = 2,
+        timeout = Duration::from_millis(10)
+    );
+}
+
+#[test]
+fn reports_mismatched_calls() {
+    let mut publisher = Publisher::faux();
+    faux::when!(publisher.publish).then_return(true);
+
+    let waiting = faux::wait_for!(
+        async publisher.publish("news"),
+        timeout = Duration::from_millis(10)
+    );
+    publisher.publish("weather");
+
+    let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        futures::executor::block_on(waiting)
+    }))
+    .unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert!(message.contains("but only 0 matched"), "{}", message);
+    assert!(
+        message.contains("1 other call(s) did not match:"),
+        "{}",
+        message
+    );
+    assert!(message.contains("weather"), "{}", message);
+}
+
+#[test]
+#[should_panic(expected = "timed out after 10ms")]
+fn async_times_out() {
+    let mut publisher = Publisher::faux();
+    faux::when!(publisher.flush).then_return(());
+
+    futures::executor::block_on(faux::wait_for!(
+        async publisher.flush,
+        timeout = Duration::from_millis(10)
+    ));
+}
+
+#[test]
+#[should_panic(expected = "cannot wait for calls to a method that was never stubbed")]
+fn unstubbed() {
+    let publisher = Publisher::faux();
+    faux::wait_for!(publisher.publish, timeout = Duration::from_millis(10));
+}